@@ -2,7 +2,7 @@ use std::env;
 use glacier_texture::enums::{RenderFormat, TextureType};
 use glacier_texture::image::{TextureMapDecoder, TextureMapEncoder};
 use glacier_texture::mipblock::MipblockData;
-use glacier_texture::pack::{TextureMapBuilder, TextureMapParameters};
+use glacier_texture::pack::TextureMapBuilder;
 use glacier_texture::texture_map::TextureMap;
 use glacier_texture::{convert, WoaVersion};
 use image::{DynamicImage};
@@ -41,18 +41,12 @@ fn main_low_level(input_path: PathBuf, output_path: PathBuf) -> Result<(), Box<d
     //read image using image-rs
     let jpeg = image::open(input_path)?;
 
-    //Set up texture parameters
-    let mut params = TextureMapParameters::new(RenderFormat::BC7);
-    params.set_texture_type(TextureType::Colour);
-
     //encode image to TEXT and TEXD buffers
-    let enc = TextureMapEncoder::new(
-        text_writer,
-        Some(texd_writer),
-        WoaVersion::HM3,
-        Some(params),
-        None,
-    );
+    let enc = TextureMapEncoder::from_writers(text_writer, Some(texd_writer))
+        .with_woa_version(WoaVersion::HM3)
+        .with_format(RenderFormat::BC7)
+        .with_texture_type(TextureType::Colour)
+        .with_mipblock1(true);
     jpeg.write_with_encoder(enc)?;
 
     //read buffers into texture_map
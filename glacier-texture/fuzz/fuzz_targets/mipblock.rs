@@ -0,0 +1,20 @@
+#![no_main]
+
+use glacier_texture::mipblock::MipblockData;
+use glacier_texture::WoaVersion;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((version, data)) = data.split_first() else {
+        return;
+    };
+    let woa_version = match version % 3 {
+        0 => WoaVersion::HM2016,
+        1 => WoaVersion::HM2,
+        _ => WoaVersion::HM3,
+    };
+
+    if let Ok(mipblock) = MipblockData::from_memory(data, woa_version) {
+        let _ = mipblock.pack_to_vec(woa_version);
+    }
+});
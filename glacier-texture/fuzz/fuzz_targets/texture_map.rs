@@ -0,0 +1,24 @@
+#![no_main]
+
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((version, data)) = data.split_first() else {
+        return;
+    };
+    let woa_version = match version % 3 {
+        0 => WoaVersion::HM2016,
+        1 => WoaVersion::HM2,
+        _ => WoaVersion::HM3,
+    };
+
+    if let Ok(texture) = TextureMap::from_memory(data, woa_version) {
+        texture.mipmaps().for_each(drop);
+        let _ = texture.content_hash();
+        let _ = texture.mipblock1();
+        let _ = texture.video_memory_requirement();
+        let _ = texture.pack_to_vec();
+    }
+});
@@ -0,0 +1,125 @@
+use crate::enums::{RenderFormat, TextureType};
+use serde::{Deserialize, Serialize};
+
+/// Channel values within this distance are considered equal, this absorbs noise from lossy sources.
+const CHANNEL_TOLERANCE: u8 = 4;
+
+/// Describes how the alpha channel of an image is used.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlphaUsage {
+    /// Every pixel is fully opaque.
+    Unused,
+    /// Every pixel is either fully opaque or fully transparent.
+    OneBit,
+    /// The alpha channel contains intermediate values.
+    Gradient,
+}
+
+/// The result of inspecting the pixels of a source image, together with the proposed format.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FormatAnalysis {
+    /// The proposed render format.
+    pub format: RenderFormat,
+    pub alpha: AlphaUsage,
+    /// The red, green and blue channels hold the same value.
+    pub grayscale: bool,
+    /// Only the red and green channels carry data, blue is constant at 0 or 1.
+    pub two_channel: bool,
+    /// The colour channels are constant, only the alpha channel carries data.
+    pub alpha_only: bool,
+    /// Human-readable explanation of how the format was chosen.
+    pub reasons: Vec<String>,
+}
+
+fn near(a: u8, b: u8) -> bool {
+    a.abs_diff(b) <= CHANNEL_TOLERANCE
+}
+
+/// Texture types that are sampled through their red channel only, whatever their other channels hold.
+fn is_single_channel(texture_type: TextureType) -> bool {
+    matches!(texture_type, TextureType::Height | TextureType::UNKNOWN512)
+}
+
+/// Inspects tightly packed RGBA8 pixels and proposes a format for the given texture type.
+pub(crate) fn analyze_rgba8(pixels: &[u8], texture_type: TextureType) -> FormatAnalysis {
+    let mut opaque = true;
+    let mut one_bit = true;
+    let mut grayscale = true;
+    let mut blue_unused = true;
+    let mut constant_colour = true;
+
+    let first = pixels.get(0..4).unwrap_or(&[0, 0, 0, 0xFF]);
+    for pixel in pixels.chunks_exact(4) {
+        let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+        opaque &= near(a, 0xFF);
+        one_bit &= near(a, 0xFF) || near(a, 0);
+        grayscale &= near(r, g) && near(g, b);
+        blue_unused &= near(b, first[2]) && (near(b, 0) || near(b, 0xFF));
+        constant_colour &= near(r, first[0]) && near(g, first[1]) && near(b, first[2]);
+    }
+
+    let alpha = if opaque {
+        AlphaUsage::Unused
+    } else if one_bit {
+        AlphaUsage::OneBit
+    } else {
+        AlphaUsage::Gradient
+    };
+    let two_channel = blue_unused && !grayscale;
+    let alpha_only = constant_colour && alpha != AlphaUsage::Unused;
+
+    let mut reasons = vec![];
+    let format = match texture_type {
+        TextureType::Normal | TextureType::CompoundNormal => {
+            reasons.push(format!(
+                "{texture_type:?} textures only store X and Y, Z is reconstructed"
+            ));
+            RenderFormat::BC5
+        }
+        _ if alpha_only => {
+            reasons.push("the colour channels are constant, only alpha carries data".to_string());
+            RenderFormat::A8
+        }
+        _ if grayscale && alpha == AlphaUsage::Unused => {
+            reasons.push(
+                "the red, green and blue channels are equal, BC4 stores a single channel"
+                    .to_string(),
+            );
+            RenderFormat::BC4
+        }
+        _ if is_single_channel(texture_type) && alpha == AlphaUsage::Unused => {
+            reasons.push(format!(
+                "{texture_type:?} textures only use a single channel"
+            ));
+            RenderFormat::BC4
+        }
+        _ if two_channel && near(first[2], 0) && alpha == AlphaUsage::Unused => {
+            reasons.push(
+                "only red and green carry data and blue is 0, which BC5 reproduces".to_string(),
+            );
+            RenderFormat::BC5
+        }
+        _ if alpha == AlphaUsage::OneBit => {
+            reasons.push("the alpha channel is 1-bit, BC3 keeps its edges sharp".to_string());
+            RenderFormat::BC3
+        }
+        _ if alpha == AlphaUsage::Gradient => {
+            reasons
+                .push("the alpha channel contains a gradient, BC7 preserves it best".to_string());
+            RenderFormat::BC7
+        }
+        _ => {
+            reasons.push("the image is opaque colour".to_string());
+            RenderFormat::BC1
+        }
+    };
+
+    FormatAnalysis {
+        format,
+        alpha,
+        grayscale,
+        two_channel,
+        alpha_only,
+        reasons,
+    }
+}
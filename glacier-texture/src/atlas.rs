@@ -21,27 +21,58 @@ pub struct AtlasData {
     /// The amount of columns in the atlas.
     pub width: u32,
     /// The amount of rows in the atlas.
+    #[br(assert(
+        vertex_count(width, height, polygon_vertex_count).is_some(),
+        "The atlas holds too many vertices: {}x{}x{}", width, height, polygon_vertex_count
+    ))]
     pub height: u32,
 
-    #[br(count = (width * height * polygon_vertex_count) as usize)]
+    #[br(count = vertex_count(width, height, polygon_vertex_count).unwrap_or(0))]
     pub polygon_vertices: Vec<TilePolygonVertex>,
 }
 
+/// Upper bound for the amount of vertices in an atlas, real atlases stay far below this.
+const MAX_VERTICES: usize = 1 << 20;
+
+fn vertex_count(width: u32, height: u32, polygon_vertex_count: u32) -> Option<usize> {
+    (width as usize)
+        .checked_mul(height as usize)?
+        .checked_mul(polygon_vertex_count as usize)
+        .filter(|count| *count <= MAX_VERTICES)
+}
+
 impl AtlasData {
     pub fn polygon_vertex_count(&self) -> usize {
-        self.polygon_vertices.len() / (self.width * self.height) as usize
+        self.polygon_vertices
+            .len()
+            .checked_div(self.width as usize * self.height as usize)
+            .unwrap_or(0)
     }
 
     pub(crate) fn size(&self) -> usize {
         (3 * size_of::<u32>()) + (self.polygon_vertices.len() * size_of::<TilePolygonVertex>())
     }
 
+    /// Whether the tile edges that lie on a texel boundary at `from` (width, height) still do at `to`.
+    /// The texture coordinates are relative to the texture size, so a resized texture keeps its atlas,
+    /// but the tiles of a smaller texture can end up in between its texels.
+    pub(crate) fn keeps_texel_alignment(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        fn on_texel(uv: f32, size: usize) -> bool {
+            let texel = uv * size as f32;
+            (texel - texel.round()).abs() < 1e-3
+        }
+        self.polygon_vertices.iter().all(|vertex| {
+            (!on_texel(vertex.text_uv_x, from.0) || on_texel(vertex.text_uv_x, to.0))
+                && (!on_texel(vertex.text_uv_y, from.1) || on_texel(vertex.text_uv_y, to.1))
+        })
+    }
+
     /// Retrieves the polygon vertices for a specific tile given its column and row.
     pub fn get_tile_vertices(&self, column: u32, row: u32) -> Option<&[TilePolygonVertex]> {
         if column >= self.width || row >= self.height {
             return None;
         }
-        let tile_index = (row * self.width + column) as usize;
+        let tile_index = row as usize * self.width as usize + column as usize;
         let start = tile_index * self.polygon_vertex_count();
         let end = start + self.polygon_vertex_count();
         self.polygon_vertices.get(start..end)
@@ -50,7 +81,7 @@ impl AtlasData {
     /// Iterates over all tiles, yielding (column, row, &[TilePolygonVertex]).
     pub fn iter_tiles(&self) -> impl Iterator<Item=(u32, u32, &[TilePolygonVertex])> + '_ {
         self.polygon_vertices
-            .chunks(self.polygon_vertex_count())
+            .chunks(self.polygon_vertex_count().max(1))
             .enumerate()
             .map(move |(i, vertices)| {
                 let row = (i as u32) / self.width;
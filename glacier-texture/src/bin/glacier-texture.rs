@@ -0,0 +1,1145 @@
+//! Command-line tool to inspect, export, build, port, validate and analyze Glacier texture files.
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+#[cfg(feature = "unstable")]
+use glacier_texture::enums::Dimensions;
+use glacier_texture::enums::{InterpretAs, RenderFormat, TextureType};
+use glacier_texture::header_info::TextureMapHeaderInfo;
+use glacier_texture::mipblock::MipblockData;
+use glacier_texture::mipgen::MipGenerationOptions;
+use glacier_texture::pack::{
+    ChannelSource, MipFilter, MipLevels, TextureMapBuilder, TextureMapParameters, TexturePreset,
+};
+use glacier_texture::progress::ProgressHooks;
+use glacier_texture::scan::{self, DuplicateIndex, TextureStatistics};
+use glacier_texture::texture_map::{MipLevel, TextureMap};
+use glacier_texture::{compare, convert, height, WoaVersion};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser, Debug)]
+#[command(name = "glacier-texture", author, version, about, long_about = None)]
+struct Cli {
+    /// When this flag is set, only errors will be shown
+    #[arg(short, long, global = true)]
+    silent: bool,
+
+    /// Command to execute
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the header values of TEXT files
+    Info(InfoArgs),
+
+    /// Export TEXT files to DDS, TGA, PNG or KTX2 files
+    Export(ExportArgs),
+
+    /// Build a TEXT (and TEXD) file from a DDS, TGA, KTX2 or PNG image
+    Import(ImportArgs),
+
+    /// Port a TEXT (and TEXD) file to another game version without re-encoding it
+    Port(PortArgs),
+
+    /// Check that TEXT files decode and write back byte for byte
+    Validate(ValidateArgs),
+
+    /// Build a histogram of the header values of TEXT files
+    Scan(ScanArgs),
+
+    /// Group the TEXT files that share the same pixel data, TEXD files next to them are loaded
+    Duplicates(DuplicatesArgs),
+
+    /// Compare a TEXT file against another TEXT file or a source image
+    Compare(CompareArgs),
+
+    /// Pack grayscale images or constants into the channels of a TEXT (and TEXD) file
+    PackChannels(PackChannelsArgs),
+
+    /// Split TEXT files into a grayscale PNG per channel
+    SplitChannels(SplitChannelsArgs),
+
+    /// Export height TEXT files as 16-bit grayscale PNGs, with their range in the text chunks
+    ExportHeight(ExportHeightArgs),
+
+    /// Write the low resolution TEXT-only variant of a TEXT and TEXD pair
+    StripTexd(StripTexdArgs),
+}
+
+#[derive(Debug, Args)]
+struct Paths {
+    /// Version of the game the textures are from, options: [HM2016, HM2, HM3]
+    #[arg(short, long)]
+    game_version: WoaVersion,
+
+    /// TEXT files, directories to search recursively or glob patterns like `textures/**/*.TEXT`
+    #[arg(required = true)]
+    inputs: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+struct Inputs {
+    #[command(flatten)]
+    paths: Paths,
+
+    /// Path to the TEXD file of the texture, only valid for a single input file
+    #[arg(short = 'd', long)]
+    texd_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct InfoArgs {
+    #[command(flatten)]
+    inputs: Inputs,
+
+    /// Print the header values as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    Dds,
+    Tga,
+    Png,
+    Ktx2,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Dds => "dds",
+            ExportFormat::Tga => "tga",
+            ExportFormat::Png => "png",
+            ExportFormat::Ktx2 => "ktx2",
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct ExportArgs {
+    #[command(flatten)]
+    inputs: Inputs,
+
+    /// The format to export to
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Dds)]
+    format: ExportFormat,
+
+    /// Export only this mip level, supported for DDS and PNG
+    #[arg(long, conflicts_with = "all_mips")]
+    mip: Option<usize>,
+
+    /// Export every mip level to its own file, supported for DDS and PNG
+    #[arg(long)]
+    all_mips: bool,
+
+    /// The directory to export to, directory inputs keep their layout. Defaults to next to the input files
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct ImportArgs {
+    /// Version of the game you want to generate for, options: [HM2016, HM2, HM3]
+    #[arg(short, long)]
+    game_version: WoaVersion,
+
+    /// Path to the source image, DDS, TGA and KTX2 files are read directly, other formats through the image crate
+    input: PathBuf,
+
+    /// Path of the TEXT file, the TEXD is written next to it. Defaults to the input path with a TEXT extension
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Enable this to generate only a .text file
+    #[arg(long)]
+    no_texd: bool,
+
+    /// Parameter preset to start from, options: [diffuse, normal, compound-normal, height, emission, billboard, mask, ui]
+    #[arg(short, long)]
+    preset: Option<TexturePreset>,
+
+    /// Render format, options: [R16G16B16A16, R8G8B8A8, R8G8, A8, BC1, BC2, BC3, BC4, BC5, BC7]
+    #[arg(short, long, conflicts_with = "auto_format")]
+    format: Option<RenderFormat>,
+
+    /// Choose the format based on the contents of the image
+    #[arg(long)]
+    auto_format: bool,
+
+    /// Texture type, options: [colour, normal, height, compound-normal, billboard, projection, emission, cubemap, unknown512]
+    #[arg(long)]
+    texture_type: Option<TextureType>,
+
+    /// Interpret as, options: [colour, normal, height, compound-normal, billboard, cubemap, emission, volume]
+    #[arg(long)]
+    interpret_as: Option<InterpretAs>,
+
+    /// The amount of mip levels to generate, defaults to a full chain
+    #[arg(long)]
+    mip_levels: Option<u8>,
+
+    /// The mip level the game loads by default
+    #[arg(long)]
+    default_mip_level: Option<u8>,
+
+    /// Filter used to generate the mip levels, options: [nearest, linear, cubic, box]
+    #[arg(long)]
+    mip_filter: Option<MipFilter>,
+
+    /// Keep the mip levels stored in the source image and only generate the missing ones
+    #[arg(long)]
+    keep_mips: bool,
+
+    /// Preserve the fraction of pixels with an alpha above this cutoff in every mip level
+    #[arg(long)]
+    alpha_coverage_cutoff: Option<f32>,
+
+    /// Filter the colour channels in linear light
+    #[arg(long)]
+    linear_light: bool,
+
+    /// Weigh the colour channels by their alpha when filtering
+    #[arg(long)]
+    premultiplied_alpha: bool,
+
+    /// Normalize the normals of every generated mip level
+    #[arg(long)]
+    renormalize_normals: bool,
+
+    /// Overrides the deferred flag
+    #[arg(long)]
+    deferred: Option<bool>,
+
+    /// Overrides the unknown1 flag
+    #[arg(long)]
+    unknown1: Option<bool>,
+
+    /// Overrides the unknown3 flag
+    #[arg(long)]
+    unknown3: Option<bool>,
+
+    /// Treat the input as a height map and generate a BC5 normal map with this strength
+    #[arg(long)]
+    normal_from_height: Option<f32>,
+
+    /// Dimensions, options: [2d, cube, volume]
+    #[cfg(feature = "unstable")]
+    #[arg(long)]
+    dimensions: Option<Dimensions>,
+
+    /// The TEXD identifier stored in the header
+    #[cfg(feature = "unstable")]
+    #[arg(long)]
+    texd_identifier: Option<u32>,
+}
+
+#[derive(Debug, Args)]
+struct PortArgs {
+    /// Version of the game you want to port from, options: [HM2016, HM2, HM3]
+    #[arg(short, long)]
+    from_version: WoaVersion,
+
+    /// Version of the game you want to port to, options: [HM2016, HM2, HM3]
+    #[arg(short, long)]
+    to_version: WoaVersion,
+
+    /// Path to the input .text file
+    input: PathBuf,
+
+    /// Path to a .texd file
+    #[arg(short = 'd', long)]
+    texd_path: Option<PathBuf>,
+
+    /// Path of the TEXT file, the TEXD is written next to it
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Enable this to generate only a .text file
+    #[arg(long)]
+    no_texd: bool,
+}
+
+#[derive(Debug, Args)]
+struct ValidateArgs {
+    #[command(flatten)]
+    inputs: Inputs,
+}
+
+#[derive(Debug, Args)]
+struct ScanArgs {
+    #[command(flatten)]
+    paths: Paths,
+
+    /// Write the histogram as CSV instead of JSON
+    #[arg(long)]
+    csv: bool,
+
+    /// The amount of example files to keep per combination
+    #[arg(long, default_value_t = 5)]
+    max_examples: usize,
+
+    /// The file to write the histogram to. Defaults to the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct DuplicatesArgs {
+    #[command(flatten)]
+    paths: Paths,
+
+    /// The file to write the groups of duplicates to as JSON. Defaults to the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct CompareArgs {
+    /// Version of the game the texture is from, options: [HM2016, HM2, HM3]
+    #[arg(short, long)]
+    game_version: WoaVersion,
+
+    /// Path to the TEXT file
+    input: PathBuf,
+
+    /// Path to the TEXT file or source image (png, tga, dds) to compare against
+    other: PathBuf,
+
+    /// Path to the TEXD file of the texture
+    #[arg(short = 'd', long)]
+    texd_path: Option<PathBuf>,
+
+    /// Path to the TEXD file of the other texture
+    #[arg(long)]
+    other_texd_path: Option<PathBuf>,
+
+    /// Version of the game the other texture is from, defaults to the game version
+    #[arg(long)]
+    other_game_version: Option<WoaVersion>,
+
+    /// Write a heatmap of the differences in the largest compared mip level to this PNG file
+    #[arg(long)]
+    heatmap: Option<PathBuf>,
+
+    /// The file to write the comparison to as JSON. Defaults to the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct PackChannelsArgs {
+    /// Version of the game you want to generate for, options: [HM2016, HM2, HM3]
+    #[arg(short, long)]
+    game_version: WoaVersion,
+
+    /// Grayscale image or constant value (0-255) for the red channel, defaults to 0
+    #[arg(long)]
+    red: Option<String>,
+
+    /// Grayscale image or constant value (0-255) for the green channel, defaults to 0
+    #[arg(long)]
+    green: Option<String>,
+
+    /// Grayscale image or constant value (0-255) for the blue channel, defaults to 0
+    #[arg(long)]
+    blue: Option<String>,
+
+    /// Grayscale image or constant value (0-255) for the alpha channel, defaults to 255
+    #[arg(long)]
+    alpha: Option<String>,
+
+    /// Path of the TEXT file, the TEXD is written next to it
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Enable this to generate only a .text file
+    #[arg(long)]
+    no_texd: bool,
+
+    /// Parameter preset to use, options: [diffuse, normal, compound-normal, height, emission, billboard, mask, ui]
+    #[arg(short, long)]
+    preset: Option<TexturePreset>,
+}
+
+#[derive(Debug, Args)]
+struct SplitChannelsArgs {
+    #[command(flatten)]
+    inputs: Inputs,
+
+    /// The mip level to split
+    #[arg(long, default_value_t = 0)]
+    mip: usize,
+
+    /// The directory to export to, directory inputs keep their layout. Defaults to next to the input files
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct ExportHeightArgs {
+    #[command(flatten)]
+    inputs: Inputs,
+
+    /// The mip level to export
+    #[arg(long, default_value_t = 0)]
+    mip: usize,
+
+    /// The directory to export to, directory inputs keep their layout. Defaults to next to the input files
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct StripTexdArgs {
+    /// Version of the game the texture is from, options: [HM2016, HM2, HM3]
+    #[arg(short, long)]
+    game_version: WoaVersion,
+
+    /// Path to the input .text file
+    input: PathBuf,
+
+    /// Path to the .texd file holding the full mip chain
+    #[arg(short = 'd', long)]
+    texd_path: PathBuf,
+
+    /// Path of the TEXT-only file
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+/// A TEXT file to process, together with its path relative to the directory it was found in.
+struct Input {
+    path: PathBuf,
+    relative: PathBuf,
+}
+
+impl Paths {
+    /// Expands the directories and glob patterns to the TEXT files they contain.
+    fn resolve(&self) -> Result<Vec<Input>> {
+        let mut files = vec![];
+        for input in &self.inputs {
+            let path = Path::new(input);
+            if path.is_dir() {
+                for file in scan::find_texture_files(path)
+                    .with_context(|| format!("Failed to list the files in {input:?}"))?
+                {
+                    let relative = file.strip_prefix(path).unwrap_or(&file).to_path_buf();
+                    files.push(Input {
+                        path: file,
+                        relative,
+                    });
+                }
+            } else if input.contains(['*', '?', '[']) {
+                for file in
+                    glob::glob(input).with_context(|| format!("Invalid glob pattern {input:?}"))?
+                {
+                    let file = file?;
+                    if file.is_file() {
+                        files.push(Input::file(file));
+                    }
+                }
+            } else {
+                files.push(Input::file(path.to_path_buf()));
+            }
+        }
+
+        if files.is_empty() {
+            bail!("No TEXT files found in {:?}", self.inputs);
+        }
+        Ok(files)
+    }
+}
+
+impl Inputs {
+    fn resolve(&self) -> Result<Vec<Input>> {
+        let files = self.paths.resolve()?;
+        if self.texd_path.is_some() && files.len() > 1 {
+            bail!("A TEXD file can only be given for a single input file");
+        }
+        Ok(files)
+    }
+
+    fn read(&self, input: &Input) -> Result<TextureMap> {
+        read_texture(
+            &input.path,
+            self.texd_path.as_deref(),
+            self.paths.game_version,
+        )
+    }
+}
+
+impl Input {
+    fn file(path: PathBuf) -> Self {
+        let relative = path.file_name().map(PathBuf::from).unwrap_or_default();
+        Self { path, relative }
+    }
+
+    /// The path to derive output file names from, in `output` if given, otherwise next to the input.
+    fn output_base(&self, output: Option<&Path>) -> PathBuf {
+        match output {
+            Some(output) => output.join(&self.relative),
+            None => self.path.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TextureInfo {
+    path: PathBuf,
+    #[serde(flatten)]
+    header: TextureMapHeaderInfo,
+    /// The amount of mip levels stored in the TEXD.
+    text_scale: usize,
+    /// The mip levels that can be read, this excludes the TEXD mips when the TEXD isn't loaded.
+    available_mip_levels: usize,
+    has_texd: bool,
+    video_memory_requirement: usize,
+}
+
+impl TextureInfo {
+    fn new(path: &Path, texture: &TextureMap) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            header: texture
+                .header_info()
+                .context("Failed to read the header values")?,
+            text_scale: texture.text_scale(),
+            available_mip_levels: texture.num_mip_levels(),
+            has_texd: texture.has_mipblock1(),
+            video_memory_requirement: texture.video_memory_requirement(),
+        })
+    }
+
+    fn print(&self) {
+        println!("{}", self.path.display());
+        for line in self.header.to_string().lines() {
+            println!("  {line}");
+        }
+        println!("  text_scale: {}", self.text_scale);
+        println!("  available_mip_levels: {}", self.available_mip_levels);
+        println!("  texd_loaded: {}", self.has_texd);
+        println!(
+            "  video_memory_requirement: {}",
+            self.video_memory_requirement
+        );
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Info(cmd) => info(cmd),
+        Command::Export(cmd) => export(cmd, cli.silent),
+        Command::Import(cmd) => import(cmd, cli.silent).map(|_| true),
+        Command::Port(cmd) => port(cmd, cli.silent).map(|_| true),
+        Command::Validate(cmd) => validate(cmd, cli.silent),
+        Command::Scan(cmd) => scan(cmd, cli.silent),
+        Command::Duplicates(cmd) => duplicates(cmd, cli.silent),
+        Command::Compare(cmd) => compare(cmd, cli.silent).map(|_| true),
+        Command::PackChannels(cmd) => pack_channels(cmd, cli.silent).map(|_| true),
+        Command::SplitChannels(cmd) => split_channels(cmd, cli.silent),
+        Command::ExportHeight(cmd) => export_height(cmd, cli.silent),
+        Command::StripTexd(cmd) => strip_texd(cmd, cli.silent).map(|_| true),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs `process` for every input and reports the failures.
+/// Returns whether every input was processed successfully.
+fn run_batch(inputs: &[Input], mut process: impl FnMut(&Input) -> Result<()>) -> bool {
+    let mut failures = 0;
+    for input in inputs {
+        if let Err(e) = process(input) {
+            eprintln!("{}: {e:#}", input.path.display());
+            failures += 1;
+        }
+    }
+    if failures > 0 && inputs.len() > 1 {
+        eprintln!("{failures} of {} textures failed", inputs.len());
+    }
+    failures == 0
+}
+
+fn info(cmd: InfoArgs) -> Result<bool> {
+    let inputs = cmd.inputs.resolve()?;
+    let mut infos = vec![];
+    let success = run_batch(&inputs, |input| {
+        let info = TextureInfo::new(&input.path, &cmd.inputs.read(input)?)?;
+        if !cmd.json {
+            info.print();
+        }
+        infos.push(info);
+        Ok(())
+    });
+
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&infos)
+                .context("Failed to serialize the header values")?
+        );
+    }
+    Ok(success)
+}
+
+fn export(cmd: ExportArgs, silent: bool) -> Result<bool> {
+    if (cmd.mip.is_some() || cmd.all_mips)
+        && !matches!(cmd.format, ExportFormat::Dds | ExportFormat::Png)
+    {
+        bail!("Single mip levels can only be exported as DDS or PNG");
+    }
+
+    let inputs = cmd.inputs.resolve()?;
+    Ok(run_batch(&inputs, |input| {
+        let texture = cmd.inputs.read(input)?;
+        let base_path = input.output_base(cmd.output.as_deref());
+
+        let levels = match cmd.mip {
+            Some(level) => vec![Some(level)],
+            None if cmd.all_mips => (0..texture.num_mip_levels()).map(Some).collect(),
+            None => vec![None],
+        };
+        for level in levels {
+            let data = match level {
+                Some(level) => export_mip(&texture, cmd.format, level),
+                None => export_texture(&texture, cmd.format),
+            }?;
+
+            let output_path = match level {
+                Some(level) => {
+                    with_suffix(&base_path, &format!("mip{level}"), cmd.format.extension())
+                }
+                None => base_path.with_extension(cmd.format.extension()),
+            };
+            write_export(&output_path, data, silent)?;
+        }
+        Ok(())
+    }))
+}
+
+/// `path` with `_suffix` appended to its file stem and its extension replaced.
+fn with_suffix(path: &Path, suffix: &str, extension: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}_{suffix}.{extension}"))
+}
+
+/// Writes an exported file, creating the directories it is in.
+fn write_export(path: &Path, data: Vec<u8>, silent: bool) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create the directory {parent:?}"))?;
+    }
+    fs::write(path, data).with_context(|| format!("Failed to write {path:?}"))?;
+
+    if !silent {
+        println!("Exported {path:?}");
+    }
+    Ok(())
+}
+
+/// Writes a report to `output`, or to the standard output if no file is given.
+fn write_report(output: Option<&Path>, report: String) -> Result<()> {
+    match output {
+        Some(output) => {
+            fs::write(output, report).with_context(|| format!("Failed to write {output:?}"))
+        }
+        None => {
+            println!("{report}");
+            Ok(())
+        }
+    }
+}
+
+fn export_texture(texture: &TextureMap, format: ExportFormat) -> Result<Vec<u8>> {
+    Ok(match format {
+        ExportFormat::Dds => convert::create_dds(texture)?,
+        ExportFormat::Tga => convert::create_tga(texture)?,
+        ExportFormat::Png => encode_png(texture, 0)?,
+        ExportFormat::Ktx2 => convert::create_ktx2(texture)?,
+    })
+}
+
+fn export_mip(texture: &TextureMap, format: ExportFormat, level: usize) -> Result<Vec<u8>> {
+    if level >= texture.num_mip_levels() {
+        bail!(
+            "Mip level {level} is not available, the texture has {} mip levels",
+            texture.num_mip_levels()
+        );
+    }
+    Ok(match format {
+        ExportFormat::Dds => convert::create_mip_dds(texture, level, false)?,
+        ExportFormat::Png => encode_png(texture, level)?,
+        ExportFormat::Tga | ExportFormat::Ktx2 => {
+            bail!("Single mip levels can only be exported as DDS or PNG")
+        }
+    })
+}
+
+/// Height textures are written as 16-bit grayscale, with their range in the `HeightMin` and `HeightMax` text chunks.
+fn encode_png(texture: &TextureMap, level: usize) -> Result<Vec<u8>> {
+    if texture.is_height_map() {
+        return Ok(height::create_height_png(texture, level)?.png);
+    }
+
+    let mip = convert::decode_mipmap(texture, level)?;
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, mip.width as u32, mip.height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&mip.data)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+fn import(cmd: ImportArgs, silent: bool) -> Result<()> {
+    let data = fs::read(&cmd.input).with_context(|| format!("Failed to read {:?}", cmd.input))?;
+    let extension = cmd
+        .input
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    let builder = match extension.as_deref() {
+        Some("dds") => TextureMapBuilder::from_dds(data.as_slice()),
+        Some("tga") => TextureMapBuilder::from_tga(data.as_slice()),
+        Some("ktx2") => TextureMapBuilder::from_ktx2(data.as_slice()),
+        _ => TextureMapBuilder::from_dynamic_image(
+            image::load_from_memory(&data)
+                .with_context(|| format!("Failed to decode {:?}", cmd.input))?,
+        ),
+    }
+    .context("Failed to read the source image")?;
+
+    let mut params = match cmd.preset {
+        Some(preset) => TextureMapParameters::from_preset(preset, cmd.game_version),
+        None => *builder.params(),
+    };
+    if let Some(format) = cmd.format {
+        params.set_format(format);
+    }
+    if let Some(texture_type) = cmd.texture_type {
+        params.set_texture_type(texture_type);
+    }
+    if let Some(interpret_as) = cmd.interpret_as {
+        params.set_interpret_as(interpret_as);
+    }
+    if let Some(levels) = cmd.mip_levels {
+        params.set_num_mip_levels(MipLevels::Limit(levels));
+    }
+    if let Some(level) = cmd.default_mip_level {
+        params.set_default_mip_level(level);
+    }
+    if let Some(mip_filter) = cmd.mip_filter {
+        params.set_mip_filter(mip_filter);
+    }
+    params.set_mip_generation(MipGenerationOptions {
+        alpha_coverage_cutoff: cmd.alpha_coverage_cutoff,
+        linear_light: cmd.linear_light,
+        premultiplied_alpha: cmd.premultiplied_alpha,
+        renormalize_normals: cmd.renormalize_normals,
+    });
+
+    let mut flags = params.flags();
+    if let Some(deferred) = cmd.deferred {
+        flags.set_deferred(deferred);
+    }
+    if let Some(unknown1) = cmd.unknown1 {
+        flags.set_unknown1(unknown1);
+    }
+    if let Some(unknown3) = cmd.unknown3 {
+        flags.set_unknown3(unknown3);
+    }
+    params.set_flags(flags);
+
+    #[cfg(feature = "unstable")]
+    {
+        if let Some(dimensions) = cmd.dimensions {
+            params.set_dimensions(dimensions);
+        }
+        if let Some(texd_identifier) = cmd.texd_identifier {
+            params.set_texd_identifier(texd_identifier);
+        }
+    }
+
+    let mut builder = builder
+        .with_params(params)
+        .with_text_only(cmd.no_texd)
+        .with_auto_format(cmd.auto_format)
+        .with_authored_mips(cmd.keep_mips);
+    if let Some(strength) = cmd.normal_from_height {
+        builder = builder.with_normal_from_height(strength);
+    }
+
+    let texture = builder
+        .build(cmd.game_version)
+        .context("Failed to build the texture")?;
+    let text_path = cmd
+        .output
+        .unwrap_or_else(|| cmd.input.with_extension("TEXT"));
+    write_texture(&texture, &text_path, cmd.game_version, silent)
+}
+
+fn port(cmd: PortArgs, silent: bool) -> Result<()> {
+    let texture = read_texture(&cmd.input, cmd.texd_path.as_deref(), cmd.from_version)?;
+
+    let mut ported = texture
+        .ported(cmd.to_version)
+        .context("Failed to port the texture, the TEXD might be missing")?;
+    if cmd.no_texd {
+        ported = ported
+            .text_only()
+            .context("Failed to strip the TEXD mips")?;
+    }
+
+    write_texture(&ported, &cmd.output, cmd.to_version, silent)
+}
+
+fn validate(cmd: ValidateArgs, silent: bool) -> Result<bool> {
+    let inputs = cmd.inputs.resolve()?;
+    let woa_version = cmd.inputs.paths.game_version;
+    Ok(run_batch(&inputs, |input| {
+        let texture = cmd.inputs.read(input)?;
+
+        for (level, mip) in texture.all_mipmaps().into_iter().enumerate() {
+            mip.with_context(|| format!("Mip level {level} can't be read"))?;
+        }
+        convert::decode_mipmap(&texture, 0).context("The largest mip level can't be decoded")?;
+
+        let original =
+            fs::read(&input.path).with_context(|| format!("Failed to read {:?}", input.path))?;
+        if let Some(offset) = texture.verify_lossless(&original)? {
+            bail!("The TEXT differs from the written texture at byte {offset:#x}");
+        }
+
+        if let Some(texd_path) = &cmd.inputs.texd_path {
+            let original =
+                fs::read(texd_path).with_context(|| format!("Failed to read {texd_path:?}"))?;
+            let mipblock = MipblockData::from_memory(&original, woa_version)
+                .context("Failed to read the TEXD")?;
+            if let Some(offset) = mipblock.verify_lossless(&original, woa_version)? {
+                bail!("The TEXD differs from the written texture at byte {offset:#x}");
+            }
+        }
+
+        if !silent {
+            println!("{}: OK", input.path.display());
+        }
+        Ok(())
+    }))
+}
+
+fn scan(cmd: ScanArgs, silent: bool) -> Result<bool> {
+    let files = cmd
+        .paths
+        .resolve()?
+        .into_iter()
+        .map(|input| input.path)
+        .collect::<Vec<_>>();
+    let mut statistics = TextureStatistics::new(cmd.max_examples);
+    scan::scan_files(
+        &files,
+        cmd.paths.game_version,
+        &mut statistics,
+        &ProgressHooks::default(),
+    )?;
+
+    let report = if cmd.csv {
+        statistics.to_csv()
+    } else {
+        serde_json::to_string_pretty(&statistics.entries())
+            .context("Failed to serialize the statistics")?
+    };
+    write_report(cmd.output.as_deref(), report)?;
+
+    for (id, error) in statistics.failures() {
+        eprintln!("{id}: {error}");
+    }
+    if !silent {
+        eprintln!(
+            "Scanned {} textures, {} failed to parse",
+            statistics.total(),
+            statistics.failures().len()
+        );
+    }
+    Ok(statistics.failures().is_empty())
+}
+
+fn duplicates(cmd: DuplicatesArgs, silent: bool) -> Result<bool> {
+    let files = cmd
+        .paths
+        .resolve()?
+        .into_iter()
+        .map(|input| input.path)
+        .collect::<Vec<_>>();
+    let mut index = DuplicateIndex::new();
+    scan::hash_files(
+        &files,
+        cmd.paths.game_version,
+        &mut index,
+        &ProgressHooks::default(),
+    )?;
+
+    let duplicates = index.duplicates();
+    let report =
+        serde_json::to_string_pretty(&duplicates).context("Failed to serialize the duplicates")?;
+    write_report(cmd.output.as_deref(), report)?;
+
+    for (id, error) in index.failures() {
+        eprintln!("{id}: {error}");
+    }
+    if !silent {
+        eprintln!(
+            "Hashed {} textures, found {} groups of duplicates, {} failed to parse",
+            index.total(),
+            duplicates.len(),
+            index.failures().len()
+        );
+    }
+    Ok(index.failures().is_empty())
+}
+
+fn compare(cmd: CompareArgs, silent: bool) -> Result<()> {
+    let texture = read_texture(&cmd.input, cmd.texd_path.as_deref(), cmd.game_version)?;
+
+    let is_texture_map = cmd
+        .other
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("text"));
+    let (comparison, other_mip) = if is_texture_map {
+        let other = read_texture(
+            &cmd.other,
+            cmd.other_texd_path.as_deref(),
+            cmd.other_game_version.unwrap_or(cmd.game_version),
+        )?;
+        let comparison = compare::compare_texture_maps(&texture, &other)
+            .context("Failed to compare the textures")?;
+        let other_mip = comparison
+            .mips
+            .first()
+            .map(|mip| convert::decode_mipmap(&other, mip.right_level))
+            .transpose()
+            .context("Failed to decode the other texture")?;
+        (comparison, other_mip)
+    } else {
+        let image = image::open(&cmd.other)
+            .with_context(|| format!("Failed to read the image {:?}", cmd.other))?
+            .to_rgba8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let comparison = compare::compare_with_image(&texture, width, height, image.as_raw())
+            .context("Failed to compare the texture against the image")?;
+        let other_mip = comparison.mips.first().map(|mip| {
+            let resized = image::imageops::resize(
+                &image,
+                mip.width as u32,
+                mip.height as u32,
+                image::imageops::FilterType::Triangle,
+            );
+            MipLevel {
+                format: RenderFormat::R8G8B8A8,
+                width: mip.width,
+                height: mip.height,
+                data: resized.into_raw(),
+            }
+        });
+        (comparison, other_mip)
+    };
+
+    if let (Some(heatmap_path), Some(mip), Some(other_mip)) =
+        (&cmd.heatmap, comparison.mips.first(), other_mip)
+    {
+        let decoded = convert::decode_mipmap(&texture, mip.left_level)
+            .context("Failed to decode the texture")?;
+        let channels = mip
+            .channels
+            .iter()
+            .map(|channel| channel.channel)
+            .collect::<Vec<_>>();
+        let heatmap = compare::difference_heatmap(&decoded, &other_mip, &channels)
+            .context("Failed to create the heatmap")?;
+        write_export(heatmap_path, heatmap, silent)?;
+    }
+
+    let report =
+        serde_json::to_string_pretty(&comparison).context("Failed to serialize the comparison")?;
+    write_report(cmd.output.as_deref(), report)?;
+
+    if !silent {
+        eprintln!(
+            "Compared {} mip levels, lowest PSNR: {:.2} dB, {} header differences",
+            comparison.mips.len(),
+            comparison.min_psnr(),
+            comparison.header_differences.len()
+        );
+    }
+    Ok(())
+}
+
+/// Reads a channel argument, either a constant value or the path to an image that is converted to grayscale.
+/// The size of the first image is stored in `size`, every other image has to match it.
+fn channel_source(
+    argument: Option<&str>,
+    default: u8,
+    size: &mut Option<(u32, u32)>,
+) -> Result<ChannelSource> {
+    let Some(argument) = argument else {
+        return Ok(ChannelSource::Constant(default));
+    };
+    if let Ok(constant) = argument.parse::<u8>() {
+        return Ok(ChannelSource::Constant(constant));
+    }
+
+    let image = image::open(argument)
+        .with_context(|| format!("Failed to read the image {argument:?}"))?
+        .to_luma8();
+    let dimensions = image.dimensions();
+    let expected = *size.get_or_insert(dimensions);
+    if expected != dimensions {
+        bail!(
+            "{argument:?} is {}x{}, the other channels are {}x{}",
+            dimensions.0,
+            dimensions.1,
+            expected.0,
+            expected.1
+        );
+    }
+    Ok(ChannelSource::Grayscale(image.into_raw()))
+}
+
+fn pack_channels(cmd: PackChannelsArgs, silent: bool) -> Result<()> {
+    let mut size = None;
+    let channels = [
+        channel_source(cmd.red.as_deref(), 0, &mut size)?,
+        channel_source(cmd.green.as_deref(), 0, &mut size)?,
+        channel_source(cmd.blue.as_deref(), 0, &mut size)?,
+        channel_source(cmd.alpha.as_deref(), 0xFF, &mut size)?,
+    ];
+    let (width, height) = size.context("At least one channel needs to be an image")?;
+
+    let mut builder = TextureMapBuilder::from_channels(width, height, channels)
+        .context("Failed to pack the channels")?
+        .with_text_only(cmd.no_texd);
+    if let Some(preset) = cmd.preset {
+        builder = builder.with_params(TextureMapParameters::from_preset(preset, cmd.game_version));
+    }
+
+    let texture = builder
+        .build(cmd.game_version)
+        .context("Failed to build the texture")?;
+    write_texture(&texture, &cmd.output, cmd.game_version, silent)
+}
+
+fn split_channels(cmd: SplitChannelsArgs, silent: bool) -> Result<bool> {
+    let inputs = cmd.inputs.resolve()?;
+    Ok(run_batch(&inputs, |input| {
+        let texture = cmd.inputs.read(input)?;
+        let pngs = convert::create_channel_pngs(&texture, cmd.mip)
+            .context("Failed to split the texture into channels")?;
+
+        let base_path = input.output_base(cmd.output.as_deref());
+        for (channel, png) in pngs {
+            let channel = format!("{channel:?}").to_lowercase();
+            write_export(&with_suffix(&base_path, &channel, "png"), png, silent)?;
+        }
+        Ok(())
+    }))
+}
+
+fn export_height(cmd: ExportHeightArgs, silent: bool) -> Result<bool> {
+    let inputs = cmd.inputs.resolve()?;
+    Ok(run_batch(&inputs, |input| {
+        let texture = cmd.inputs.read(input)?;
+        if !texture.is_height_map() && !silent {
+            eprintln!(
+                "{}: the texture is not marked as a height map",
+                input.path.display()
+            );
+        }
+
+        let height_png = height::create_height_png(&texture, cmd.mip)
+            .context("Failed to export the height map")?;
+        let output_path = input
+            .output_base(cmd.output.as_deref())
+            .with_extension("png");
+        write_export(&output_path, height_png.png, silent)?;
+        if !silent {
+            println!(
+                "  heights range from {} to {}",
+                height_png.min, height_png.max
+            );
+        }
+        Ok(())
+    }))
+}
+
+fn strip_texd(cmd: StripTexdArgs, silent: bool) -> Result<()> {
+    let texture = read_texture(&cmd.input, Some(&cmd.texd_path), cmd.game_version)?;
+    let text_only = texture
+        .text_only()
+        .context("Failed to strip the TEXD mips")?;
+    write_texture(&text_only, &cmd.output, cmd.game_version, silent)
+}
+
+fn read_texture(
+    path: &Path,
+    texd_path: Option<&Path>,
+    woa_version: WoaVersion,
+) -> Result<TextureMap> {
+    let mut texture = TextureMap::from_file(path, woa_version)
+        .with_context(|| format!("Failed to read the TEXT file {path:?}"))?;
+    if let Some(texd_path) = texd_path {
+        texture.set_mipblock1(
+            MipblockData::from_file(texd_path, woa_version)
+                .with_context(|| format!("Failed to read the TEXD file {texd_path:?}"))?,
+        );
+    }
+    Ok(texture)
+}
+
+/// Writes the TEXT file and, if the texture holds its full mip chain, the TEXD next to it.
+fn write_texture(
+    texture: &TextureMap,
+    text_path: &Path,
+    woa_version: WoaVersion,
+    silent: bool,
+) -> Result<()> {
+    fs::write(
+        text_path,
+        texture.pack_to_vec().context("Failed to pack TEXT data")?,
+    )
+    .with_context(|| format!("Failed to write TEXT file to {text_path:?}"))?;
+    if !silent {
+        println!("Wrote TEXT at {text_path:?}");
+    }
+
+    if texture.has_mipblock1() {
+        let texd_path = text_path.with_extension("TEXD");
+        let mipblock = texture
+            .mipblock1()
+            .context("Failed to retrieve TEXD data from the texture")?;
+        fs::write(
+            &texd_path,
+            mipblock
+                .pack_to_vec(woa_version)
+                .context("Failed to pack TEXD data")?,
+        )
+        .with_context(|| format!("Failed to write TEXD file to {texd_path:?}"))?;
+        if !silent {
+            println!("Wrote TEXD at {texd_path:?}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,413 @@
+use crate::convert;
+use crate::convert::TextureConversionError;
+use crate::enums::{Channel, RenderFormat};
+use crate::texture_map::{MipLevel, TextureMap};
+use png::ColorType;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Size of the square window used for SSIM.
+const SSIM_WINDOW: usize = 8;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelStatistics {
+    pub channel: Channel,
+    /// Peak signal-to-noise ratio in dB, infinite when the channel is identical.
+    pub psnr: f64,
+    pub max_error: u8,
+    pub ssim: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MipComparison {
+    /// The mip level in the left texture.
+    pub left_level: usize,
+    /// The mip level in the right texture or image chain.
+    pub right_level: usize,
+    pub width: usize,
+    pub height: usize,
+    pub channels: Vec<ChannelStatistics>,
+}
+
+/// A header value that differs between the two textures.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HeaderDifference {
+    pub field: String,
+    pub left: String,
+    pub right: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TextureComparison {
+    pub header_differences: Vec<HeaderDifference>,
+    /// Statistics of every mip level that is present at the same size on both sides.
+    pub mips: Vec<MipComparison>,
+    /// Sizes of the mip levels that are only present on one side.
+    pub unmatched_mips: Vec<(usize, usize)>,
+}
+
+impl TextureComparison {
+    /// True when the headers match and every compared channel is identical.
+    pub fn is_identical(&self) -> bool {
+        self.header_differences.is_empty()
+            && self.unmatched_mips.is_empty()
+            && self
+                .mips
+                .iter()
+                .flat_map(|mip| &mip.channels)
+                .all(|channel| channel.max_error == 0)
+    }
+
+    /// The lowest PSNR over every compared mip level and channel.
+    pub fn min_psnr(&self) -> f64 {
+        self.mips
+            .iter()
+            .flat_map(|mip| &mip.channels)
+            .map(|channel| channel.psnr)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// Compares the headers and the decoded pixels of two textures.
+/// Mip levels are paired by size, so a TEXT-only texture can be compared against one with its TEXD.
+pub fn compare_texture_maps(
+    left: &TextureMap,
+    right: &TextureMap,
+) -> Result<TextureComparison, TextureConversionError> {
+    let mut channels = Channel::stored_by(left.format());
+    for channel in Channel::stored_by(right.format()) {
+        if !channels.contains(&channel) {
+            channels.push(channel);
+        }
+    }
+    channels.sort_by_key(Channel::index);
+
+    let left_mips = decode_mipmaps(left)?;
+    let right_mips = decode_mipmaps(right)?;
+    let mut comparison = compare_mip_chains(&left_mips, &right_mips, &channels);
+    comparison.header_differences = header_differences(left, right);
+    Ok(comparison)
+}
+
+/// Compares a texture against the source image it was built from, given as tightly packed RGBA8 pixels.
+/// The source is downsampled with a box filter to match the smaller mip levels.
+pub fn compare_with_image(
+    texture: &TextureMap,
+    width: usize,
+    height: usize,
+    rgba8: &[u8],
+) -> Result<TextureComparison, TextureConversionError> {
+    if width == 0 || height == 0 {
+        return Err(TextureConversionError::InvalidTexture(format!(
+            "The source image is {width}x{height}, both sides have to be larger than 0"
+        )));
+    }
+    if rgba8.len() < width * height * 4 {
+        return Err(TextureConversionError::InvalidTexture(format!(
+            "The source image holds {} bytes, expected {}",
+            rgba8.len(),
+            width * height * 4
+        )));
+    }
+
+    let mut source_mips = vec![MipLevel {
+        format: RenderFormat::R8G8B8A8,
+        width,
+        height,
+        data: rgba8[..width * height * 4].to_vec(),
+    }];
+    while let Some(last) = source_mips
+        .last()
+        .filter(|mip| mip.width > 1 || mip.height > 1)
+    {
+        let next = downsample(last);
+        source_mips.push(next);
+    }
+
+    let texture_mips = decode_mipmaps(texture)?;
+    Ok(compare_mip_chains(
+        &texture_mips,
+        &source_mips,
+        &Channel::stored_by(texture.format()),
+    ))
+}
+
+/// Renders the per-pixel differences between two RGBA8 mip levels as a PNG.
+/// The largest difference over the given channels is shown, scaled from black over red and yellow to white.
+pub fn difference_heatmap(
+    left: &MipLevel,
+    right: &MipLevel,
+    channels: &[Channel],
+) -> Result<Vec<u8>, TextureConversionError> {
+    if (left.width, left.height) != (right.width, right.height) {
+        return Err(TextureConversionError::InvalidTexture(format!(
+            "Can't compare a {}x{} mip level against a {}x{} one",
+            left.width, left.height, right.width, right.height
+        )));
+    }
+    if left.format != RenderFormat::R8G8B8A8 || right.format != RenderFormat::R8G8B8A8 {
+        return Err(TextureConversionError::InvalidTexture(
+            "Both mip levels need to be decoded to RGBA8".to_string(),
+        ));
+    }
+
+    let errors = left
+        .data
+        .chunks_exact(4)
+        .zip(right.data.chunks_exact(4))
+        .map(|(l, r)| {
+            channels
+                .iter()
+                .map(|channel| l[channel.index()].abs_diff(r[channel.index()]))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let max_error = errors.iter().copied().max().unwrap_or(0).max(1) as f32;
+
+    let pixels = errors
+        .iter()
+        .flat_map(|error| {
+            let t = *error as f32 / max_error * 3.0;
+            [t, t - 1.0, t - 2.0].map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8)
+        })
+        .collect::<Vec<_>>();
+
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, left.width as u32, left.height as u32);
+    encoder.set_color(ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(io::Error::from)?;
+    writer.write_image_data(&pixels).map_err(io::Error::from)?;
+    writer.finish().map_err(io::Error::from)?;
+    Ok(png)
+}
+
+/// Decodes every mip level. The smallest levels of a non-square texture are 0 pixels wide or high,
+/// those are skipped as there is nothing to compare in them.
+fn decode_mipmaps(texture: &TextureMap) -> Result<Vec<MipLevel>, TextureConversionError> {
+    (0..texture.num_mip_levels())
+        .take_while(|level| texture.width() >> level > 0 && texture.height() >> level > 0)
+        .map(|level| convert::decode_mipmap(texture, level))
+        .collect()
+}
+
+fn compare_mip_chains(
+    left: &[MipLevel],
+    right: &[MipLevel],
+    channels: &[Channel],
+) -> TextureComparison {
+    let mut comparison = TextureComparison::default();
+    for (left_level, left_mip) in left.iter().enumerate() {
+        let matching = right
+            .iter()
+            .position(|mip| (mip.width, mip.height) == (left_mip.width, left_mip.height));
+        match matching {
+            Some(right_level) => comparison.mips.push(MipComparison {
+                left_level,
+                right_level,
+                width: left_mip.width,
+                height: left_mip.height,
+                channels: channels
+                    .iter()
+                    .map(|channel| channel_statistics(left_mip, &right[right_level], *channel))
+                    .collect(),
+            }),
+            None => comparison
+                .unmatched_mips
+                .push((left_mip.width, left_mip.height)),
+        }
+    }
+    for right_mip in right {
+        if !left
+            .iter()
+            .any(|mip| (mip.width, mip.height) == (right_mip.width, right_mip.height))
+        {
+            comparison
+                .unmatched_mips
+                .push((right_mip.width, right_mip.height));
+        }
+    }
+    comparison
+}
+
+fn channel_statistics(left: &MipLevel, right: &MipLevel, channel: Channel) -> ChannelStatistics {
+    let index = channel.index();
+    let left_values = left
+        .data
+        .chunks_exact(4)
+        .map(|pixel| pixel[index])
+        .collect::<Vec<_>>();
+    let right_values = right
+        .data
+        .chunks_exact(4)
+        .map(|pixel| pixel[index])
+        .collect::<Vec<_>>();
+
+    let mut max_error = 0;
+    let mut squared_error = 0.0;
+    for (l, r) in left_values.iter().zip(&right_values) {
+        let error = l.abs_diff(*r);
+        max_error = max_error.max(error);
+        squared_error += (error as f64).powi(2);
+    }
+    let mse = squared_error / left_values.len().max(1) as f64;
+    let psnr = if mse == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 / mse).log10()
+    };
+
+    ChannelStatistics {
+        channel,
+        psnr,
+        max_error,
+        ssim: ssim(&left_values, &right_values, left.width, left.height),
+    }
+}
+
+/// Mean SSIM over overlapping windows, small levels are treated as a single window.
+fn ssim(left: &[u8], right: &[u8], width: usize, height: usize) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let window_width = SSIM_WINDOW.min(width);
+    let window_height = SSIM_WINDOW.min(height);
+    let step = SSIM_WINDOW / 2;
+
+    let mut total = 0.0;
+    let mut windows = 0;
+    for y in (0..=height - window_height).step_by(step) {
+        for x in (0..=width - window_width).step_by(step) {
+            let samples = (y..y + window_height)
+                .flat_map(|wy| (x..x + window_width).map(move |wx| wy * width + wx))
+                .map(|i| (left[i] as f64, right[i] as f64))
+                .collect::<Vec<_>>();
+            let n = samples.len() as f64;
+            let mean_left = samples.iter().map(|(l, _)| l).sum::<f64>() / n;
+            let mean_right = samples.iter().map(|(_, r)| r).sum::<f64>() / n;
+            let (mut var_left, mut var_right, mut covariance) = (0.0, 0.0, 0.0);
+            for (l, r) in &samples {
+                var_left += (l - mean_left).powi(2);
+                var_right += (r - mean_right).powi(2);
+                covariance += (l - mean_left) * (r - mean_right);
+            }
+            let (var_left, var_right, covariance) = (var_left / n, var_right / n, covariance / n);
+
+            total += ((2.0 * mean_left * mean_right + C1) * (2.0 * covariance + C2))
+                / ((mean_left.powi(2) + mean_right.powi(2) + C1) * (var_left + var_right + C2));
+            windows += 1;
+        }
+    }
+    total / windows.max(1) as f64
+}
+
+fn downsample(mip: &MipLevel) -> MipLevel {
+    let width = (mip.width / 2).max(1);
+    let height = (mip.height / 2).max(1);
+    let mut data = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0u32; 4];
+            let mut count = 0;
+            for (sx, sy) in
+                [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| (x * 2 + dx, y * 2 + dy))
+            {
+                if sx < mip.width && sy < mip.height {
+                    let i = (sy * mip.width + sx) * 4;
+                    sum.iter_mut()
+                        .zip(&mip.data[i..i + 4])
+                        .for_each(|(s, c)| *s += *c as u32);
+                    count += 1;
+                }
+            }
+            data.extend(sum.map(|c| ((c + count / 2) / count) as u8));
+        }
+    }
+    MipLevel {
+        format: RenderFormat::R8G8B8A8,
+        width,
+        height,
+        data,
+    }
+}
+
+fn header_differences(left: &TextureMap, right: &TextureMap) -> Vec<HeaderDifference> {
+    let fields = [
+        (
+            "version",
+            format!("{:?}", left.version()),
+            format!("{:?}", right.version()),
+        ),
+        (
+            "type",
+            format!("{:?}", left.texture_type()),
+            format!("{:?}", right.texture_type()),
+        ),
+        (
+            "interpret_as",
+            format!("{:?}", left.interpret_as()),
+            format!("{:?}", right.interpret_as()),
+        ),
+        (
+            "format",
+            format!("{:?}", left.format()),
+            format!("{:?}", right.format()),
+        ),
+        (
+            "flags",
+            format!("{:#x}", left.flags().bits()),
+            format!("{:#x}", right.flags().bits()),
+        ),
+        (
+            "dimensions",
+            format!("{:?}", left.dimensions()),
+            format!("{:?}", right.dimensions()),
+        ),
+        (
+            "size",
+            format!("{:?}", left.texd_size()),
+            format!("{:?}", right.texd_size()),
+        ),
+        (
+            "num_mip_levels",
+            left.texd_mip_levels().to_string(),
+            right.texd_mip_levels().to_string(),
+        ),
+        (
+            "default_mip_level",
+            left.default_mip_level().to_string(),
+            right.default_mip_level().to_string(),
+        ),
+        (
+            "text_scale",
+            left.text_scale().to_string(),
+            right.text_scale().to_string(),
+        ),
+        (
+            "has_atlas",
+            left.has_atlas().to_string(),
+            right.has_atlas().to_string(),
+        ),
+        (
+            "mip_sizes",
+            format!("{:?}", left.mip_sizes()),
+            format!("{:?}", right.mip_sizes()),
+        ),
+        (
+            "compressed_mip_sizes",
+            format!("{:?}", left.compressed_mip_sizes()),
+            format!("{:?}", right.compressed_mip_sizes()),
+        ),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, left, right)| left != right)
+        .map(|(field, left, right)| HeaderDifference {
+            field: field.to_string(),
+            left,
+            right,
+        })
+        .collect()
+}
@@ -1,5 +1,7 @@
 use crate::convert::TextureConversionError::DirectXTexError;
-use crate::enums::RenderFormat;
+use crate::enums::{Channel, Dimensions, RenderFormat};
+use crate::height;
+use crate::ktx2;
 use crate::texture_map::{MipLevel, TextureMap};
 use directxtex::{
     HResultError, Image, ScratchImage, TexMetadata, CP_FLAGS, DDS_FLAGS, DXGI_FORMAT,
@@ -33,9 +35,11 @@ pub enum TextureConversionError {
 
 /// Converts a `TextureMap` into a DDS (DirectDraw Surface) image file.
 pub fn create_dds(tex: &TextureMap) -> Result<Vec<u8>, TextureConversionError> {
-    let mut mips = (0..tex.num_mip_levels())
-        .filter_map(|i| -> Option<MipLevel> {
-            if let Ok(mip) = tex.mipmap(i) {
+    let mut mips = tex
+        .all_mipmaps()
+        .into_iter()
+        .filter_map(|mip| -> Option<MipLevel> {
+            if let Ok(mip) = mip {
                 if mip.height > 0 && mip.width > 0 {
                     Some(mip)
                 } else {
@@ -69,6 +73,7 @@ pub fn create_dds(tex: &TextureMap) -> Result<Vec<u8>, TextureConversionError> {
             let pitch = DXGI_FORMAT::from(tex.format())
                 .compute_pitch(mip.width, mip.height, CP_FLAGS::CP_FLAGS_NONE)
                 .map_err(DirectXTexError)?;
+            check_mip_size(mip, pitch.slice)?;
 
             Ok(Image {
                 width: mip.width,
@@ -92,6 +97,42 @@ pub fn create_dds(tex: &TextureMap) -> Result<Vec<u8>, TextureConversionError> {
     Ok(Vec::from(blob.buffer()))
 }
 
+/// Converts a `TextureMap` into a KTX2 file, the mip levels are copied without re-encoding.
+/// Cubemaps are written with six faces if every mip level holds them.
+/// A8 textures are stored as `VK_FORMAT_A8_UNORM_KHR`.
+pub fn create_ktx2(tex: &TextureMap) -> Result<Vec<u8>, TextureConversionError> {
+    let mips = tex
+        .all_mipmaps()
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TextureConversionError::InvalidTexture(format!("Failed to read the mip levels: {e}")))?
+        .into_iter()
+        .take_while(|mip| mip.height > 0 && mip.width > 0)
+        .collect::<Vec<_>>();
+
+    let first_mip = mips.first().ok_or(TextureConversionError::InvalidTexture(
+        "There are no textures in the data".to_string(),
+    ))?;
+
+    let format = DXGI_FORMAT::from(tex.format());
+    let mut face_count = 1;
+    if tex.dimensions() == Dimensions::Cube {
+        let all_faces = mips.iter().try_fold(true, |all_faces, mip| {
+            let pitch = format
+                .compute_pitch(mip.width, mip.height, CP_FLAGS::CP_FLAGS_NONE)
+                .map_err(DirectXTexError)?;
+            Ok::<_, TextureConversionError>(all_faces && mip.data.len() == pitch.slice * 6)
+        })?;
+        if all_faces {
+            face_count = 6;
+        }
+    }
+
+    let (width, height) = (first_mip.width, first_mip.height);
+    let mips = mips.into_iter().map(|mip| mip.data).collect::<Vec<_>>();
+    ktx2::write_ktx2(tex.format(), width, height, face_count, &mips)
+        .map_err(|e| TextureConversionError::InvalidTexture(format!("Failed to write KTX2: {e}")))
+}
 
 /// Converts a `TextureMap` into a TGA (Targa) image file.
 /// # Warning
@@ -116,7 +157,12 @@ pub fn create_tga(tex: &TextureMap) -> Result<Vec<u8>, TextureConversionError> {
 }
 
 /// Converts a `TextureMap` into a PNG image file.
+/// Height maps are exported with [`height::create_height_png`], as 16-bit grayscale with their range in the text chunks.
 pub fn create_png(tex: &TextureMap) -> Result<Vec<u8>, TextureConversionError> {
+    if tex.is_height_map() {
+        return Ok(height::create_height_png(tex, 0)?.png);
+    }
+
     let dds = create_dds(tex)?;
     let mut scratch_image = ScratchImage::load_dds(
         dds.as_slice(),
@@ -174,6 +220,20 @@ pub fn create_dynamic_image(tex: &TextureMap) -> ImageResult<DynamicImage> {
     DynamicImage::from_decoder(TextureMapDecoder::from_texture_map(tex.clone()))
 }
 
+/// Makes sure DirectXTex never reads past the end of a mip level.
+fn check_mip_size(mip: &MipLevel, expected: usize) -> Result<(), TextureConversionError> {
+    if mip.data.len() < expected {
+        return Err(TextureConversionError::InvalidTexture(format!(
+            "A {}x{} mip level holds {} bytes, expected {}",
+            mip.width,
+            mip.height,
+            mip.data.len(),
+            expected
+        )));
+    }
+    Ok(())
+}
+
 pub(crate) fn decompress_dds(
     tex: &TextureMap,
     scratch_image: ScratchImage,
@@ -227,6 +287,118 @@ pub(crate) fn decompress_dds(
     Ok(scratch_image)
 }
 
+/// Decodes a single mip level to tightly packed RGBA8 pixels.
+/// Channels the format doesn't store are left at the decoder defaults, A8 keeps its value in alpha.
+pub fn decode_mipmap(tex: &TextureMap, mip_level: usize) -> Result<MipLevel, TextureConversionError> {
+    let dds = create_mip_dds(tex, mip_level, false)?;
+    let image = ScratchImage::load_dds(
+        dds.as_slice(),
+        DDS_FLAGS::DDS_FLAGS_FORCE_DX10_EXT,
+        None,
+        None,
+    )
+    .map_err(DirectXTexError)?;
+
+    let rgba8 = DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM;
+    let image = if tex.format().is_compressed() {
+        directxtex::decompress(image.images(), image.metadata(), rgba8).map_err(DirectXTexError)?
+    } else if tex.format() != RenderFormat::R8G8B8A8 {
+        directxtex::convert(
+            image.images(),
+            image.metadata(),
+            rgba8,
+            TEX_FILTER_FLAGS::TEX_FILTER_DEFAULT | TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC,
+            TEX_THRESHOLD_DEFAULT,
+        )
+        .map_err(DirectXTexError)?
+    } else {
+        image
+    };
+
+    let (width, height) = (image.metadata().width, image.metadata().height);
+    let data = image
+        .pixels()
+        .get(..width * height * 4)
+        .ok_or(TextureConversionError::InvalidTexture(format!(
+            "Mip level {mip_level} decoded to fewer pixels than expected"
+        )))?
+        .to_vec();
+    Ok(MipLevel {
+        format: RenderFormat::R8G8B8A8,
+        width,
+        height,
+        data,
+    })
+}
+
+/// Splits a mip level into a grayscale PNG for every channel the format stores.
+pub fn create_channel_pngs(
+    tex: &TextureMap,
+    mip_level: usize,
+) -> Result<Vec<(Channel, Vec<u8>)>, TextureConversionError> {
+    let mip = decode_mipmap(tex, mip_level)?;
+    Channel::stored_by(tex.format())
+        .into_iter()
+        .map(|channel| {
+            let values = mip
+                .data
+                .chunks_exact(4)
+                .map(|pixel| pixel[channel.index()])
+                .collect::<Vec<_>>();
+
+            let mut png = vec![];
+            let mut encoder = png::Encoder::new(&mut png, mip.width as u32, mip.height as u32);
+            encoder.set_color(ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().map_err(io::Error::from)?;
+            writer.write_image_data(&values).map_err(io::Error::from)?;
+            writer.finish().map_err(io::Error::from)?;
+            Ok((channel, png))
+        })
+        .collect()
+}
+
+/// Resizes a mip level that was decoded with [`decode_mipmap`] using a linear filter.
+pub(crate) fn resize_rgba8(
+    mut mip: MipLevel,
+    width: usize,
+    height: usize,
+) -> Result<MipLevel, TextureConversionError> {
+    let format = DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM;
+    let pitch = format
+        .compute_pitch(mip.width, mip.height, CP_FLAGS::CP_FLAGS_NONE)
+        .map_err(DirectXTexError)?;
+    if mip.data.len() < pitch.slice {
+        return Err(TextureConversionError::InvalidTexture(format!(
+            "The mip level holds {} bytes, expected {}",
+            mip.data.len(),
+            pitch.slice
+        )));
+    }
+
+    let image = Image {
+        width: mip.width,
+        height: mip.height,
+        format,
+        row_pitch: pitch.row,
+        slice_pitch: pitch.slice,
+        pixels: mip.data.as_mut_ptr(),
+    };
+    let resized = image
+        .resize(
+            width,
+            height,
+            TEX_FILTER_FLAGS::TEX_FILTER_LINEAR | TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC,
+        )
+        .map_err(DirectXTexError)?;
+    Ok(MipLevel {
+        format: RenderFormat::R8G8B8A8,
+        width,
+        height,
+        data: resized.pixels()[..width * height * 4].to_vec(),
+    })
+}
+
 pub fn create_mip_dds(
     tex: &TextureMap,
     mip_level: usize,
@@ -247,6 +419,7 @@ pub fn create_mip_dds(
         let pitch = DXGI_FORMAT::from(tex.format())
             .compute_pitch(mip.width, mip.height, CP_FLAGS::CP_FLAGS_NONE)
             .map_err(DirectXTexError)?;
+        check_mip_size(&mip, pitch.slice)?;
 
         let image = Image {
             width: mip.width,
@@ -2,9 +2,10 @@ use binrw::{BinRead, BinWrite};
 use bitfield_struct::bitfield;
 use directxtex::{DXGI_FORMAT, TEX_DIMENSION};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[brw(repr = u16)]
 pub enum TextureType
 {
@@ -24,7 +25,25 @@ pub enum TextureType
     //UNKNOWN1024 = 1024, //unused
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+impl FromStr for TextureType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "colour" | "color" => Ok(TextureType::Colour),
+            "normal" => Ok(TextureType::Normal),
+            "height" => Ok(TextureType::Height),
+            "compoundnormal" | "compound_normal" | "compound-normal" => Ok(TextureType::CompoundNormal),
+            "billboard" => Ok(TextureType::Billboard),
+            "projection" => Ok(TextureType::Projection),
+            "emission" => Ok(TextureType::Emission),
+            "cubemap" => Ok(TextureType::Cubemap),
+            "unknown512" => Ok(TextureType::UNKNOWN512),
+            _ => Err(format!("Invalid value for TextureType: {s}")),
+        }
+    }
+}
+
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[brw(repr = u8)]
 pub enum InterpretAs
 {
@@ -39,10 +58,26 @@ pub enum InterpretAs
     Volume = 64, //This as well
 }
 
+impl FromStr for InterpretAs {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "colour" | "color" => Ok(InterpretAs::Colour),
+            "normal" => Ok(InterpretAs::Normal),
+            "height" => Ok(InterpretAs::Height),
+            "compoundnormal" | "compound_normal" | "compound-normal" => Ok(InterpretAs::CompoundNormal),
+            "billboard" => Ok(InterpretAs::Billboard),
+            "cubemap" => Ok(InterpretAs::Cubemap),
+            "emission" => Ok(InterpretAs::Emission),
+            "volume" => Ok(InterpretAs::Volume),
+            _ => Err(format!("Invalid value for InterpretAs: {s}")),
+        }
+    }
+}
 
 #[derive(BinRead, BinWrite, Serialize, Deserialize, Debug, Copy)]
 #[brw(repr = u16)]
-#[derive(Clone, PartialEq, Hash, Eq)]
+#[derive(Clone, PartialEq, Hash, Eq, PartialOrd, Ord)]
 pub enum RenderFormat
 {
     R16G16B16A16 = 0x0A,
@@ -67,6 +102,11 @@ impl RenderFormat {
             RenderFormat::BC7)
     }
 
+    /// Whether the channels are stored as floats, which can hold values outside of the 0-1 range.
+    pub(crate) fn is_float(&self) -> bool {
+        matches!(self, RenderFormat::R16G16B16A16)
+    }
+
     pub fn num_channels(&self) -> usize {
         match self {
             RenderFormat::A8 | RenderFormat::BC4 => 1,
@@ -79,6 +119,66 @@ impl RenderFormat {
             RenderFormat::BC7 => 4,
         }
     }
+
+    /// The size in bytes of a single 4x4 block, or of a single pixel for uncompressed formats.
+    pub(crate) fn block_size(&self) -> usize {
+        match self {
+            RenderFormat::BC1 | RenderFormat::BC4 | RenderFormat::R16G16B16A16 => 8,
+            RenderFormat::BC2 | RenderFormat::BC3 | RenderFormat::BC5 | RenderFormat::BC7 => 16,
+            RenderFormat::R8G8B8A8 => 4,
+            RenderFormat::R8G8 => 2,
+            RenderFormat::A8 => 1,
+        }
+    }
+}
+
+impl FromStr for RenderFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "R16G16B16A16" | "RGBA16F" => Ok(RenderFormat::R16G16B16A16),
+            "R8G8B8A8" | "RGBA8" => Ok(RenderFormat::R8G8B8A8),
+            "R8G8" | "RG8" => Ok(RenderFormat::R8G8),
+            "A8" => Ok(RenderFormat::A8),
+            "BC1" | "DXT1" => Ok(RenderFormat::BC1),
+            "BC2" | "DXT3" => Ok(RenderFormat::BC2),
+            "BC3" | "DXT5" => Ok(RenderFormat::BC3),
+            "BC4" => Ok(RenderFormat::BC4),
+            "BC5" => Ok(RenderFormat::BC5),
+            "BC7" => Ok(RenderFormat::BC7),
+            _ => Err(format!("Invalid value for RenderFormat: {s}")),
+        }
+    }
+}
+
+/// A single colour channel of a decoded RGBA8 image.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Channel {
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Channel::Red => 0,
+            Channel::Green => 1,
+            Channel::Blue => 2,
+            Channel::Alpha => 3,
+        }
+    }
+
+    /// The channels that carry data for the given format, once decoded to RGBA8.
+    pub fn stored_by(format: RenderFormat) -> Vec<Channel> {
+        match format {
+            RenderFormat::A8 => vec![Channel::Alpha],
+            RenderFormat::BC4 => vec![Channel::Red],
+            RenderFormat::R8G8 | RenderFormat::BC5 => vec![Channel::Red, Channel::Green],
+            _ => vec![Channel::Red, Channel::Green, Channel::Blue, Channel::Alpha],
+        }
+    }
 }
 
 impl From<RenderFormat> for DXGI_FORMAT {
@@ -122,7 +222,7 @@ impl TryFrom<DXGI_FORMAT> for RenderFormat {
     }
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[brw(repr = u8)]
 pub enum Dimensions
 {
@@ -132,6 +232,18 @@ pub enum Dimensions
     Volume = 2,
 }
 
+impl FromStr for Dimensions {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "2d" => Ok(Dimensions::_2D),
+            "cube" | "cubemap" => Ok(Dimensions::Cube),
+            "volume" | "3d" => Ok(Dimensions::Volume),
+            _ => Err(format!("Invalid value for Dimensions: {s}")),
+        }
+    }
+}
+
 impl From<Dimensions> for TEX_DIMENSION {
     fn from(val: Dimensions) -> Self {
         match val {
@@ -176,6 +288,9 @@ impl TextureFlags{
     pub fn atlas(&self) -> bool { self.inner.atlas() }
     pub fn unknown3(&self) -> bool { self.inner.unknown3() }
 
+    /// The raw bitfield as stored in the file, including the bits without a getter.
+    pub fn bits(&self) -> u32 { self.inner.into_bits() }
+
     pub fn set_deferred(&mut self, value: bool) {
         self.inner.set_deferred(value)
     }
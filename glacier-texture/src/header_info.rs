@@ -0,0 +1,354 @@
+//! A read-only view of every value stored in a TEXT header.
+//!
+//! Unlike the getters on [`TextureMap`], the view also holds the values that are normally
+//! recalculated when packing, like the data size, the atlas offset and the HM3 TEXT scaling data.
+
+use crate::enums::{Dimensions, InterpretAs, RenderFormat, TextureType};
+use crate::texture_map::{
+    PreservedFields, TextureMap, TextureMapError, TextureMapHeaderV1, TextureMapHeaderV2,
+    TextureMapHeaderV3, TextureMapVersion, MAX_MIP_LEVELS,
+};
+use crate::WoaVersion;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// An enum field as it is stored, with the variant it decodes to, if any.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct StoredEnum<R, T> {
+    pub raw: R,
+    pub decoded: Option<T>,
+}
+
+impl<R: fmt::Display, T: fmt::Debug> fmt::Display for StoredEnum<R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.decoded {
+            Some(decoded) => write!(f, "{decoded:?}"),
+            None => write!(f, "unknown ({})", self.raw),
+        }
+    }
+}
+
+impl From<TextureType> for StoredEnum<u16, TextureType> {
+    fn from(value: TextureType) -> Self {
+        Self {
+            raw: value as u16,
+            decoded: Some(value),
+        }
+    }
+}
+
+impl From<RenderFormat> for StoredEnum<u16, RenderFormat> {
+    fn from(value: RenderFormat) -> Self {
+        Self {
+            raw: value as u16,
+            decoded: Some(value),
+        }
+    }
+}
+
+impl From<InterpretAs> for StoredEnum<u8, InterpretAs> {
+    fn from(value: InterpretAs) -> Self {
+        Self {
+            raw: value as u8,
+            decoded: Some(value),
+        }
+    }
+}
+
+impl From<Dimensions> for StoredEnum<u8, Dimensions> {
+    fn from(value: Dimensions) -> Self {
+        Self {
+            raw: value as u8,
+            decoded: Some(value),
+        }
+    }
+}
+
+/// The header of a HM2016 texture.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TextureMapHeaderInfoV1 {
+    pub num_textures: u16,
+    pub texture_type: StoredEnum<u16, TextureType>,
+    pub texd_identifier: u32,
+    /// The size of the file, minus the first 8 bytes.
+    pub data_size: u32,
+    /// The raw flags bitfield.
+    pub flags: u32,
+    pub width: u16,
+    pub height: u16,
+    pub format: StoredEnum<u16, RenderFormat>,
+    pub num_mip_levels: u8,
+    pub default_mip_level: u8,
+    pub interpret_as: StoredEnum<u8, InterpretAs>,
+    pub dimensions: StoredEnum<u8, Dimensions>,
+    pub mips_interpolation_deprecated: u16,
+    /// The cumulative size of the mip levels.
+    pub mip_sizes: [u32; MAX_MIP_LEVELS],
+    pub atlas_data_size: u32,
+    pub atlas_data_offset: u32,
+}
+
+/// The header of a HM2 texture.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TextureMapHeaderInfoV2 {
+    pub num_textures: u16,
+    pub texture_type: StoredEnum<u16, TextureType>,
+    pub data_size: u32,
+    /// The raw flags bitfield.
+    pub flags: u32,
+    pub width: u16,
+    pub height: u16,
+    pub format: StoredEnum<u16, RenderFormat>,
+    pub num_mip_levels: u8,
+    pub default_mip_level: u8,
+    pub texd_identifier: u32,
+    /// The cumulative size of the mip levels.
+    pub mip_sizes: [u32; MAX_MIP_LEVELS],
+    /// The cumulative size of the mip levels as stored, HM2 doesn't compress them.
+    pub compressed_mip_sizes: [u32; MAX_MIP_LEVELS],
+    pub atlas_data_size: u32,
+    pub atlas_data_offset: u32,
+}
+
+/// The header of a HM3 texture.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TextureMapHeaderInfoV3 {
+    pub num_textures: u16,
+    pub texture_type: StoredEnum<u16, TextureType>,
+    pub data_size: u32,
+    /// The raw flags bitfield.
+    pub flags: u32,
+    pub width: u16,
+    pub height: u16,
+    pub format: StoredEnum<u16, RenderFormat>,
+    pub num_mip_levels: u8,
+    pub default_mip_level: u8,
+    pub interpret_as: StoredEnum<u8, InterpretAs>,
+    pub dimensions: StoredEnum<u8, Dimensions>,
+    pub mips_interpolation_deprecated: u16,
+    /// The cumulative size of the mip levels.
+    pub mip_sizes: [u32; MAX_MIP_LEVELS],
+    /// The cumulative size of the LZ4 compressed mip levels.
+    pub compressed_mip_sizes: [u32; MAX_MIP_LEVELS],
+    pub atlas_data_size: u32,
+    pub atlas_data_offset: u32,
+    /// Unknown, 0xFF in every shipped texture.
+    pub text_scaling_data1: u8,
+    pub text_scaling_width: u8,
+    pub text_scaling_height: u8,
+    pub text_mip_levels: u8,
+    pub padding: u32,
+}
+
+impl TextureMapHeaderInfoV1 {
+    fn new(header: &TextureMapHeaderV1, fields: PreservedFields) -> Self {
+        Self {
+            num_textures: fields.num_textures,
+            texture_type: header.type_.into(),
+            texd_identifier: header.texd_identifier,
+            data_size: fields.data_size,
+            flags: header.flags.into_bits(),
+            width: header.width,
+            height: header.height,
+            format: header.format.into(),
+            num_mip_levels: header.num_mip_levels,
+            default_mip_level: header.default_mip_level,
+            interpret_as: header.interpret_as.into(),
+            dimensions: header.dimensions.into(),
+            mips_interpolation_deprecated: fields.mips_interpolation_deprecated,
+            mip_sizes: header.mip_sizes,
+            atlas_data_size: fields.atlas_data_size,
+            atlas_data_offset: fields.atlas_data_offset,
+        }
+    }
+}
+
+impl TextureMapHeaderInfoV2 {
+    fn new(header: &TextureMapHeaderV2, fields: PreservedFields) -> Self {
+        Self {
+            num_textures: fields.num_textures,
+            texture_type: header.type_.into(),
+            data_size: fields.data_size,
+            flags: header.flags.into_bits(),
+            width: header.width,
+            height: header.height,
+            format: header.format.into(),
+            num_mip_levels: header.num_mip_levels,
+            default_mip_level: header.default_mip_level,
+            texd_identifier: header.texd_identifier,
+            mip_sizes: header.mip_sizes,
+            compressed_mip_sizes: header.compressed_mip_sizes,
+            atlas_data_size: fields.atlas_data_size,
+            atlas_data_offset: fields.atlas_data_offset,
+        }
+    }
+}
+
+impl TextureMapHeaderInfoV3 {
+    fn new(header: &TextureMapHeaderV3, fields: PreservedFields) -> Self {
+        let [text_scaling_data1, text_scaling_width, text_scaling_height, text_mip_levels] =
+            fields.text_scaling;
+        Self {
+            num_textures: fields.num_textures,
+            texture_type: header.type_.into(),
+            data_size: fields.data_size,
+            flags: header.flags.into_bits(),
+            width: header.width,
+            height: header.height,
+            format: header.format.into(),
+            num_mip_levels: header.num_mip_levels,
+            default_mip_level: header.default_mip_level,
+            interpret_as: header.interpret_as.into(),
+            dimensions: header.dimensions.into(),
+            mips_interpolation_deprecated: fields.mips_interpolation_deprecated,
+            mip_sizes: header.mip_sizes,
+            compressed_mip_sizes: header.compressed_mip_sizes,
+            atlas_data_size: fields.atlas_data_size,
+            atlas_data_offset: fields.atlas_data_offset,
+            text_scaling_data1,
+            text_scaling_width,
+            text_scaling_height,
+            text_mip_levels,
+            padding: fields.padding,
+        }
+    }
+}
+
+/// Every value stored in a TEXT header, tagged with the game version the layout belongs to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "version")]
+pub enum TextureMapHeaderInfo {
+    #[serde(rename = "HM2016")]
+    V1(TextureMapHeaderInfoV1),
+    #[serde(rename = "HM2")]
+    V2(TextureMapHeaderInfoV2),
+    #[serde(rename = "HM3")]
+    V3(TextureMapHeaderInfoV3),
+}
+
+impl TextureMapHeaderInfo {
+    pub fn woa_version(&self) -> WoaVersion {
+        match self {
+            TextureMapHeaderInfo::V1(_) => WoaVersion::HM2016,
+            TextureMapHeaderInfo::V2(_) => WoaVersion::HM2,
+            TextureMapHeaderInfo::V3(_) => WoaVersion::HM3,
+        }
+    }
+}
+
+impl TextureMap {
+    /// The header values as they are stored in the file. Textures that were read keep the values
+    /// of the file they were read from, built textures show the values they will be packed with.
+    pub fn header_info(&self) -> Result<TextureMapHeaderInfo, TextureMapError> {
+        Ok(match &self.inner {
+            TextureMapVersion::V1(tex) => TextureMapHeaderInfo::V1(TextureMapHeaderInfoV1::new(
+                &tex.header,
+                tex.stored_fields(true)?,
+            )),
+            TextureMapVersion::V2(tex) => TextureMapHeaderInfo::V2(TextureMapHeaderInfoV2::new(
+                &tex.header,
+                tex.stored_fields(true)?,
+            )),
+            TextureMapVersion::V3(tex) => TextureMapHeaderInfo::V3(TextureMapHeaderInfoV3::new(
+                &tex.header,
+                tex.stored_fields(true)?,
+            )),
+        })
+    }
+}
+
+fn write_mip_sizes(f: &mut fmt::Formatter<'_>, name: &str, sizes: &[u32]) -> fmt::Result {
+    let sizes = sizes
+        .iter()
+        .map(|size| size.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    writeln!(f, "{name}: [{sizes}]")
+}
+
+impl fmt::Display for TextureMapHeaderInfoV1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version: HM2016")?;
+        writeln!(f, "num_textures: {}", self.num_textures)?;
+        writeln!(f, "texture_type: {}", self.texture_type)?;
+        writeln!(f, "texd_identifier: {:#x}", self.texd_identifier)?;
+        writeln!(f, "data_size: {}", self.data_size)?;
+        writeln!(f, "flags: {:#x}", self.flags)?;
+        writeln!(f, "width: {}", self.width)?;
+        writeln!(f, "height: {}", self.height)?;
+        writeln!(f, "format: {}", self.format)?;
+        writeln!(f, "num_mip_levels: {}", self.num_mip_levels)?;
+        writeln!(f, "default_mip_level: {}", self.default_mip_level)?;
+        writeln!(f, "interpret_as: {}", self.interpret_as)?;
+        writeln!(f, "dimensions: {}", self.dimensions)?;
+        writeln!(
+            f,
+            "mips_interpolation_deprecated: {}",
+            self.mips_interpolation_deprecated
+        )?;
+        write_mip_sizes(f, "mip_sizes", &self.mip_sizes)?;
+        writeln!(f, "atlas_data_size: {}", self.atlas_data_size)?;
+        write!(f, "atlas_data_offset: {:#x}", self.atlas_data_offset)
+    }
+}
+
+impl fmt::Display for TextureMapHeaderInfoV2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version: HM2")?;
+        writeln!(f, "num_textures: {}", self.num_textures)?;
+        writeln!(f, "texture_type: {}", self.texture_type)?;
+        writeln!(f, "data_size: {}", self.data_size)?;
+        writeln!(f, "flags: {:#x}", self.flags)?;
+        writeln!(f, "width: {}", self.width)?;
+        writeln!(f, "height: {}", self.height)?;
+        writeln!(f, "format: {}", self.format)?;
+        writeln!(f, "num_mip_levels: {}", self.num_mip_levels)?;
+        writeln!(f, "default_mip_level: {}", self.default_mip_level)?;
+        writeln!(f, "texd_identifier: {:#x}", self.texd_identifier)?;
+        write_mip_sizes(f, "mip_sizes", &self.mip_sizes)?;
+        write_mip_sizes(f, "compressed_mip_sizes", &self.compressed_mip_sizes)?;
+        writeln!(f, "atlas_data_size: {}", self.atlas_data_size)?;
+        write!(f, "atlas_data_offset: {:#x}", self.atlas_data_offset)
+    }
+}
+
+impl fmt::Display for TextureMapHeaderInfoV3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version: HM3")?;
+        writeln!(f, "num_textures: {}", self.num_textures)?;
+        writeln!(f, "texture_type: {}", self.texture_type)?;
+        writeln!(f, "data_size: {}", self.data_size)?;
+        writeln!(f, "flags: {:#x}", self.flags)?;
+        writeln!(f, "width: {}", self.width)?;
+        writeln!(f, "height: {}", self.height)?;
+        writeln!(f, "format: {}", self.format)?;
+        writeln!(f, "num_mip_levels: {}", self.num_mip_levels)?;
+        writeln!(f, "default_mip_level: {}", self.default_mip_level)?;
+        writeln!(f, "interpret_as: {}", self.interpret_as)?;
+        writeln!(f, "dimensions: {}", self.dimensions)?;
+        writeln!(
+            f,
+            "mips_interpolation_deprecated: {}",
+            self.mips_interpolation_deprecated
+        )?;
+        write_mip_sizes(f, "mip_sizes", &self.mip_sizes)?;
+        write_mip_sizes(f, "compressed_mip_sizes", &self.compressed_mip_sizes)?;
+        writeln!(f, "atlas_data_size: {}", self.atlas_data_size)?;
+        writeln!(f, "atlas_data_offset: {:#x}", self.atlas_data_offset)?;
+        writeln!(f, "text_scaling_data1: {:#x}", self.text_scaling_data1)?;
+        writeln!(f, "text_scaling_width: {}", self.text_scaling_width)?;
+        writeln!(f, "text_scaling_height: {}", self.text_scaling_height)?;
+        writeln!(f, "text_mip_levels: {}", self.text_mip_levels)?;
+        write!(f, "padding: {:#x}", self.padding)
+    }
+}
+
+impl fmt::Display for TextureMapHeaderInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureMapHeaderInfo::V1(header) => fmt::Display::fmt(header, f),
+            TextureMapHeaderInfo::V2(header) => fmt::Display::fmt(header, f),
+            TextureMapHeaderInfo::V3(header) => fmt::Display::fmt(header, f),
+        }
+    }
+}
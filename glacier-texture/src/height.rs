@@ -0,0 +1,228 @@
+//! Height map helpers: full precision decoding, 16-bit PNG export and deriving normal maps.
+
+use crate::convert;
+use crate::convert::TextureConversionError;
+use crate::convert::TextureConversionError::DirectXTexError;
+use crate::enums::{Channel, InterpretAs, RenderFormat, TextureType};
+use crate::texture_map::{MipLevel, TextureMap};
+use directxtex::{
+    HResultError, ScratchImage, DDS_FLAGS, DXGI_FORMAT, TEX_FILTER_FLAGS, TEX_THRESHOLD_DEFAULT,
+};
+use png::ColorType;
+use std::io;
+
+/// A single channel of a mip level, decoded to 32-bit floats.
+#[derive(Clone, Debug)]
+pub struct HeightField {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+}
+
+/// A 16-bit grayscale PNG of a height field.
+/// The full 16-bit range maps to `min..=max`, so the original heights are `min + sample / 65535 * (max - min)`.
+/// The range is also stored in the PNG as the [`HEIGHT_MIN_KEYWORD`] and [`HEIGHT_MAX_KEYWORD`] text chunks.
+#[derive(Clone, Debug)]
+pub struct HeightPng {
+    pub png: Vec<u8>,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Keyword of the PNG text chunk that holds the height of a black sample.
+pub const HEIGHT_MIN_KEYWORD: &str = "HeightMin";
+/// Keyword of the PNG text chunk that holds the height of a white sample.
+pub const HEIGHT_MAX_KEYWORD: &str = "HeightMax";
+
+impl TextureMap {
+    /// Whether the texture holds height data, either through its type or the way it's interpreted.
+    pub fn is_height_map(&self) -> bool {
+        matches!(
+            self.texture_type(),
+            TextureType::Height | TextureType::UNKNOWN512
+        ) || self.interpret_as() == Some(InterpretAs::Height)
+    }
+}
+
+/// Decodes every pixel of the first image to RGBA 32-bit floats.
+pub(crate) fn decode_rgba32f(
+    image: &ScratchImage,
+) -> Result<(usize, usize, Vec<[f32; 4]>), HResultError> {
+    let rgba32f = DXGI_FORMAT::DXGI_FORMAT_R32G32B32A32_FLOAT;
+    let metadata = image.metadata();
+    let converted = if metadata.format.is_compressed() {
+        directxtex::decompress(image.images(), metadata, rgba32f)?
+    } else {
+        directxtex::convert(
+            image.images(),
+            metadata,
+            rgba32f,
+            TEX_FILTER_FLAGS::TEX_FILTER_DEFAULT | TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC,
+            TEX_THRESHOLD_DEFAULT,
+        )?
+    };
+
+    let (width, height) = (metadata.width, metadata.height);
+    let pixels = converted
+        .pixels()
+        .chunks_exact(16)
+        .take(width * height)
+        .map(|pixel| {
+            let channel =
+                |i: usize| f32::from_le_bytes(pixel[i * 4..i * 4 + 4].try_into().unwrap());
+            [channel(0), channel(1), channel(2), channel(3)]
+        })
+        .collect();
+    Ok((width, height, pixels))
+}
+
+/// The channel that holds the height, A8 stores its value in alpha while all other formats use red.
+pub(crate) fn height_channel(format: DXGI_FORMAT) -> Channel {
+    match format {
+        DXGI_FORMAT::DXGI_FORMAT_A8_UNORM => Channel::Alpha,
+        _ => Channel::Red,
+    }
+}
+
+/// Decodes a mip level of a height texture without reducing its precision to 8 bits.
+pub fn decode_height(
+    tex: &TextureMap,
+    mip_level: usize,
+) -> Result<HeightField, TextureConversionError> {
+    let dds = convert::create_mip_dds(tex, mip_level, false)?;
+    let image = ScratchImage::load_dds(
+        dds.as_slice(),
+        DDS_FLAGS::DDS_FLAGS_FORCE_DX10_EXT,
+        None,
+        None,
+    )
+    .map_err(DirectXTexError)?;
+
+    let channel = Channel::stored_by(tex.format())
+        .first()
+        .copied()
+        .unwrap_or(Channel::Red);
+    let (width, height, pixels) = decode_rgba32f(&image).map_err(DirectXTexError)?;
+    if pixels.len() != width * height {
+        return Err(TextureConversionError::InvalidTexture(format!(
+            "Mip level {mip_level} decoded to fewer pixels than expected"
+        )));
+    }
+    Ok(HeightField {
+        width,
+        height,
+        values: pixels
+            .into_iter()
+            .map(|pixel| pixel[channel.index()])
+            .collect(),
+    })
+}
+
+/// The heights that map to black and white, see [`create_height_png`].
+fn height_range(format: RenderFormat, field: &HeightField) -> (f32, f32) {
+    let (min, max) = if format == RenderFormat::R16G16B16A16 {
+        field
+            .values
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f32::MAX, f32::MIN), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            })
+    } else {
+        (0.0, 1.0)
+    };
+    if min > max {
+        (0.0, 1.0)
+    } else {
+        (min, max)
+    }
+}
+
+fn normalize_height(value: f32, min: f32, max: f32) -> f32 {
+    ((value - min) / (max - min).max(f32::EPSILON)).clamp(0.0, 1.0)
+}
+
+/// Decodes a mip level of a height texture to opaque RGBA8 grayscale, stretched like [`create_height_png`].
+pub(crate) fn decode_height_rgba8(
+    tex: &TextureMap,
+    mip_level: usize,
+) -> Result<MipLevel, TextureConversionError> {
+    let field = decode_height(tex, mip_level)?;
+    let (min, max) = height_range(tex.format(), &field);
+    let data = field
+        .values
+        .iter()
+        .flat_map(|value| {
+            let value = (normalize_height(*value, min, max) * 255.0).round() as u8;
+            [value, value, value, 0xFF]
+        })
+        .collect();
+    Ok(MipLevel {
+        format: RenderFormat::R8G8B8A8,
+        width: field.width,
+        height: field.height,
+        data,
+    })
+}
+
+/// Exports a mip level of a height texture as a 16-bit grayscale PNG.
+/// Normalized formats keep their `0..=1` range, floating point formats are stretched over
+/// the range of heights they actually hold.
+pub fn create_height_png(
+    tex: &TextureMap,
+    mip_level: usize,
+) -> Result<HeightPng, TextureConversionError> {
+    let field = decode_height(tex, mip_level)?;
+    let (min, max) = height_range(tex.format(), &field);
+    let samples = field
+        .values
+        .iter()
+        .flat_map(|value| {
+            let normalized = normalize_height(*value, min, max);
+            ((normalized * 65535.0).round() as u16).to_be_bytes()
+        })
+        .collect::<Vec<_>>();
+
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, field.width as u32, field.height as u32);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    encoder
+        .add_text_chunk(HEIGHT_MIN_KEYWORD.to_string(), min.to_string())
+        .map_err(io::Error::from)?;
+    encoder
+        .add_text_chunk(HEIGHT_MAX_KEYWORD.to_string(), max.to_string())
+        .map_err(io::Error::from)?;
+    let mut writer = encoder.write_header().map_err(io::Error::from)?;
+    writer.write_image_data(&samples).map_err(io::Error::from)?;
+    writer.finish().map_err(io::Error::from)?;
+    Ok(HeightPng { png, min, max })
+}
+
+/// Derives a tangent-space normal map from a height field with a Sobel filter.
+/// The texture is treated as tiling, so the edges sample the opposite side.
+/// Returns RGBA8 pixels with the normal in red, green and blue.
+pub(crate) fn normal_map_from_height(field: &HeightField, strength: f32) -> Vec<u8> {
+    let (width, height) = (field.width, field.height);
+    let sample = |x: isize, y: isize| {
+        let x = x.rem_euclid(width as isize) as usize;
+        let y = y.rem_euclid(height as isize) as usize;
+        field.values[y * width + x]
+    };
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let dx = (sample(x + 1, y - 1) + 2.0 * sample(x + 1, y) + sample(x + 1, y + 1))
+                - (sample(x - 1, y - 1) + 2.0 * sample(x - 1, y) + sample(x - 1, y + 1));
+            let dy = (sample(x - 1, y + 1) + 2.0 * sample(x, y + 1) + sample(x + 1, y + 1))
+                - (sample(x - 1, y - 1) + 2.0 * sample(x, y - 1) + sample(x + 1, y - 1));
+
+            let normal = [-dx * strength, -dy * strength, 1.0];
+            let length = normal.iter().map(|c| c * c).sum::<f32>().sqrt();
+            pixels.extend(normal.map(|c| ((c / length * 0.5 + 0.5) * 255.0).round() as u8));
+            pixels.push(255);
+        }
+    }
+    pixels
+}
@@ -1,9 +1,12 @@
 use crate::atlas::AtlasData;
 use crate::convert::create_dds;
 use crate::convert::TextureConversionError::DirectXTexError;
-use crate::enums::RenderFormat;
+#[cfg(feature = "unstable")]
+use crate::enums::Dimensions;
+use crate::enums::{InterpretAs, RenderFormat, TextureFlags, TextureType};
+use crate::mipgen::MipGenerationOptions;
 use crate::mipblock::MipblockData;
-use crate::pack::{TextureMapBuilder, TextureMapParameters, TexturePackerError};
+use crate::pack::{MipFilter, MipLevels, TextureMapBuilder, TextureMapParameters, TexturePackerError};
 use crate::texture_map::{TextureMap};
 use crate::WoaVersion;
 use binrw::BinRead;
@@ -23,6 +26,8 @@ pub enum TextureMapEncodeError {
     Packer(#[from] TexturePackerError),
     #[error("IO error {0}")]
     IOError(#[from] std::io::Error),
+    #[error("The mipblock is enabled, but there is no TEXD writer")]
+    MissingTexdWriter,
 }
 
 impl From<TextureMapEncodeError> for ImageError {
@@ -39,10 +44,64 @@ pub struct TextureMapEncoder<TW: Write, DW: Write> {
     texd_writer: Option<DW>,
     woa_version: WoaVersion,
     texture_parameters: Option<TextureMapParameters>,
+    overrides: ParameterOverrides,
     atlas_data: Option<AtlasData>,
+    use_mipblock1: bool,
+    auto_format: bool,
+}
+
+/// The parameters set through the `with_*` methods of [`TextureMapEncoder`].
+/// They are applied on top of the parameters of the source image, or the ones from [`TextureMapEncoder::with_params`].
+#[derive(Default)]
+struct ParameterOverrides {
+    format: Option<RenderFormat>,
+    texture_type: Option<TextureType>,
+    interpret_as: Option<InterpretAs>,
+    #[cfg(feature = "unstable")]
+    dimensions: Option<Dimensions>,
+    flags: Option<TextureFlags>,
+    num_mip_levels: Option<MipLevels>,
+    default_mip_level: Option<u8>,
+    mip_filter: Option<MipFilter>,
+    mip_generation: Option<MipGenerationOptions>,
+}
+
+impl ParameterOverrides {
+    fn apply(&self, mut params: TextureMapParameters) -> TextureMapParameters {
+        if let Some(format) = self.format {
+            params.set_format(format);
+        }
+        if let Some(texture_type) = self.texture_type {
+            params.set_texture_type(texture_type);
+        }
+        if let Some(interpret_as) = self.interpret_as {
+            params.set_interpret_as(interpret_as);
+        }
+        #[cfg(feature = "unstable")]
+        if let Some(dimensions) = self.dimensions {
+            params.set_dimensions(dimensions);
+        }
+        if let Some(flags) = &self.flags {
+            params.set_flags(TextureFlags { inner: flags.inner });
+        }
+        if let Some(levels) = self.num_mip_levels {
+            params.set_num_mip_levels(levels);
+        }
+        if let Some(level) = self.default_mip_level {
+            params.set_default_mip_level(level);
+        }
+        if let Some(mip_filter) = self.mip_filter {
+            params.set_mip_filter(mip_filter);
+        }
+        if let Some(mip_generation) = self.mip_generation {
+            params.set_mip_generation(mip_generation);
+        }
+        params
+    }
 }
 
 impl<TW: Write, DW: Write> TextureMapEncoder<TW, DW> {
+    /// The mipblock is enabled when a TEXD writer is given.
     pub fn new(
         text_writer: TW,
         texd_writer: Option<DW>,
@@ -51,13 +110,96 @@ impl<TW: Write, DW: Write> TextureMapEncoder<TW, DW> {
         atlas_data: Option<AtlasData>,
     ) -> TextureMapEncoder<TW, DW> {
         TextureMapEncoder {
+            use_mipblock1: texd_writer.is_some(),
             text_writer,
             texd_writer,
             woa_version,
             texture_parameters,
+            overrides: ParameterOverrides::default(),
             atlas_data,
+            auto_format: false,
         }
     }
+
+    /// Creates an encoder for HM3 that keeps the format of the source image, configure it with the `with_*` methods.
+    pub fn from_writers(text_writer: TW, texd_writer: Option<DW>) -> TextureMapEncoder<TW, DW> {
+        Self::new(text_writer, texd_writer, WoaVersion::HM3, None, None)
+    }
+
+    pub fn with_woa_version(mut self, woa_version: WoaVersion) -> Self {
+        self.woa_version = woa_version;
+        self
+    }
+
+    /// Replaces every parameter, including the ones set by earlier `with_*` calls.
+    pub fn with_params(mut self, params: TextureMapParameters) -> Self {
+        self.texture_parameters = Some(params);
+        self.overrides = ParameterOverrides::default();
+        self
+    }
+
+    pub fn with_atlas(mut self, atlas_data: AtlasData) -> Self {
+        self.atlas_data = Some(atlas_data);
+        self
+    }
+
+    /// Splits the mips over a TEXT and TEXD, the TEXD is written to the second writer.
+    pub fn with_mipblock1(mut self, enabled: bool) -> Self {
+        self.use_mipblock1 = enabled;
+        self
+    }
+
+    /// See [`TextureMapBuilder::with_auto_format`].
+    pub fn with_auto_format(mut self, enabled: bool) -> Self {
+        self.auto_format = enabled;
+        self
+    }
+
+    pub fn with_format(mut self, format: RenderFormat) -> Self {
+        self.overrides.format = Some(format);
+        self
+    }
+
+    pub fn with_texture_type(mut self, texture_type: TextureType) -> Self {
+        self.overrides.texture_type = Some(texture_type);
+        self
+    }
+
+    pub fn with_interpret_as(mut self, interpret_as: InterpretAs) -> Self {
+        self.overrides.interpret_as = Some(interpret_as);
+        self
+    }
+
+    #[cfg(feature = "unstable")]
+    pub fn with_dimensions(mut self, dimensions: Dimensions) -> Self {
+        self.overrides.dimensions = Some(dimensions);
+        self
+    }
+
+    pub fn with_flags(mut self, flags: TextureFlags) -> Self {
+        self.overrides.flags = Some(flags);
+        self
+    }
+
+    pub fn with_num_mip_levels(mut self, levels: MipLevels) -> Self {
+        self.overrides.num_mip_levels = Some(levels);
+        self
+    }
+
+    pub fn with_default_mip_level(mut self, level: u8) -> Self {
+        self.overrides.default_mip_level = Some(level);
+        self
+    }
+
+    pub fn with_mip_filter(mut self, mip_filter: MipFilter) -> Self {
+        self.overrides.mip_filter = Some(mip_filter);
+        self
+    }
+
+    pub fn with_mip_generation(mut self, mip_generation: MipGenerationOptions) -> Self {
+        self.overrides.mip_generation = Some(mip_generation);
+        self
+    }
 }
 
 impl<TW: Write, DW: Write> ImageEncoder for TextureMapEncoder<TW, DW> {
@@ -68,13 +210,18 @@ impl<TW: Write, DW: Write> ImageEncoder for TextureMapEncoder<TW, DW> {
         height: u32,
         color_type: ExtendedColorType,
     ) -> ImageResult<()> {
+        if self.use_mipblock1 && self.texd_writer.is_none() {
+            return Err(TextureMapEncodeError::MissingTexdWriter.into());
+        }
+
         let scratch_image = dynamic_image_to_scratch_image(buf, width, height, color_type)?;
         let mut builder = TextureMapBuilder::from_scratch_image(scratch_image)
-            .map_err(TextureMapEncodeError::Packer)?;
+            .map_err(TextureMapEncodeError::Packer)?
+            .with_mipblock1(self.use_mipblock1)
+            .with_auto_format(self.auto_format);
 
-        if let Some(params) = self.texture_parameters {
-            builder = builder.with_params(params);
-        }
+        let params = self.texture_parameters.unwrap_or(*builder.params());
+        builder = builder.with_params(self.overrides.apply(params));
 
         if let Some(atlas_data) = self.atlas_data {
             builder = builder.with_atlas(atlas_data);
@@ -88,7 +235,7 @@ impl<TW: Write, DW: Write> ImageEncoder for TextureMapEncoder<TW, DW> {
         let mut text_writer = self.text_writer;
         text_writer.write_all(&text_data)?;
 
-        if let Some(mut texd_writer) = self.texd_writer {
+        if let Some(mut texd_writer) = self.texd_writer.filter(|_| self.use_mipblock1) {
             if let Some(texd) = text.mipblock1() {
                 let texd_data = texd
                     .pack_to_vec(self.woa_version)
@@ -0,0 +1,321 @@
+use crate::enums::RenderFormat;
+use crate::pack::TexturePackerError;
+use crate::pack::TexturePackerError::PackingError;
+use binrw::{binrw, BinRead, BinResult, BinWrite};
+use directxtex::DXGI_FORMAT;
+use std::io::Cursor;
+
+/// The size of the identifier, header and index that precede the level index.
+const HEADER_SIZE: usize = 80;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+/// Upper bound for the level count, anything above this can't be a valid 2D texture.
+const MAX_LEVELS: u32 = 32;
+const VK_FORMAT_R8_UNORM: u32 = 9;
+/// `VK_FORMAT_A8_UNORM_KHR`, added by `VK_KHR_maintenance5`.
+const VK_FORMAT_A8_UNORM: u32 = 1000470001;
+
+#[binrw]
+#[brw(little, magic = b"\xABKTX 20\xBB\r\n\x1A\n")]
+#[derive(Debug, Clone)]
+pub(crate) struct Ktx2Header {
+    vk_format: u32,
+    type_size: u32,
+    pixel_width: u32,
+    pixel_height: u32,
+    pixel_depth: u32,
+    layer_count: u32,
+    face_count: u32,
+    level_count: u32,
+    supercompression_scheme: u32,
+
+    dfd_byte_offset: u32,
+    dfd_byte_length: u32,
+    kvd_byte_offset: u32,
+    kvd_byte_length: u32,
+    sgd_byte_offset: u64,
+    sgd_byte_length: u64,
+
+    #[br(count = level_count.clamp(1, MAX_LEVELS))]
+    levels: Vec<Ktx2Level>,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+struct Ktx2Level {
+    byte_offset: u64,
+    byte_length: u64,
+    uncompressed_byte_length: u64,
+}
+
+/// A single sample of the data format descriptor.
+struct Sample {
+    bit_offset: u16,
+    /// The bit length minus one.
+    bit_length: u8,
+    channel: u8,
+    lower: u32,
+    upper: u32,
+}
+
+impl Sample {
+    const fn block(bit_offset: u16, bit_length: u8, channel: u8) -> Self {
+        Self {
+            bit_offset,
+            bit_length,
+            channel,
+            lower: 0,
+            upper: u32::MAX,
+        }
+    }
+
+    const fn unorm8(index: u16, channel: u8) -> Self {
+        Self {
+            bit_offset: index * 8,
+            bit_length: 7,
+            channel,
+            lower: 0,
+            upper: 0xFF,
+        }
+    }
+
+    const fn float16(index: u16, channel: u8) -> Self {
+        Self {
+            bit_offset: index * 16,
+            bit_length: 15,
+            // KHR_DF_SAMPLE_DATATYPE_FLOAT | KHR_DF_SAMPLE_DATATYPE_SIGNED
+            channel: channel | 0xC0,
+            lower: 0xBF80_0000,
+            upper: 0x3F80_0000,
+        }
+    }
+}
+
+/// The Vulkan format used to store the render format.
+fn vk_format(format: RenderFormat) -> u32 {
+    match format {
+        RenderFormat::R16G16B16A16 => 97,
+        RenderFormat::R8G8B8A8 => 37,
+        RenderFormat::R8G8 => 16,
+        RenderFormat::A8 => VK_FORMAT_A8_UNORM,
+        RenderFormat::BC1 => 133,
+        RenderFormat::BC2 => 135,
+        RenderFormat::BC3 => 137,
+        RenderFormat::BC4 => 139,
+        RenderFormat::BC5 => 141,
+        RenderFormat::BC7 => 145,
+    }
+}
+
+/// Maps a Vulkan format to the matching DXGI format, sRGB variants share the data layout of their UNORM format.
+/// `VK_FORMAT_R8_UNORM` has no DXGI counterpart the builder can read, it's expanded by [`read_ktx2`].
+fn dxgi_format(vk_format: u32) -> Option<DXGI_FORMAT> {
+    match vk_format {
+        97 => Some(DXGI_FORMAT::DXGI_FORMAT_R16G16B16A16_FLOAT),
+        37 | 43 => Some(DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM),
+        16 => Some(DXGI_FORMAT::DXGI_FORMAT_R8G8_UNORM),
+        VK_FORMAT_A8_UNORM => Some(DXGI_FORMAT::DXGI_FORMAT_A8_UNORM),
+        131..=134 => Some(DXGI_FORMAT::DXGI_FORMAT_BC1_UNORM),
+        135 | 136 => Some(DXGI_FORMAT::DXGI_FORMAT_BC2_UNORM),
+        137 | 138 => Some(DXGI_FORMAT::DXGI_FORMAT_BC3_UNORM),
+        139 => Some(DXGI_FORMAT::DXGI_FORMAT_BC4_UNORM),
+        141 => Some(DXGI_FORMAT::DXGI_FORMAT_BC5_UNORM),
+        145 | 146 => Some(DXGI_FORMAT::DXGI_FORMAT_BC7_UNORM),
+        _ => None,
+    }
+}
+
+/// Builds the basic data format descriptor block for the render format.
+fn data_format_descriptor(format: RenderFormat) -> Vec<u8> {
+    let (color_model, samples) = match format {
+        RenderFormat::BC1 => (128, vec![Sample::block(0, 63, 1)]),
+        RenderFormat::BC2 => (
+            129,
+            vec![Sample::block(0, 63, 15), Sample::block(64, 63, 0)],
+        ),
+        RenderFormat::BC3 => (
+            130,
+            vec![Sample::block(0, 63, 15), Sample::block(64, 63, 0)],
+        ),
+        RenderFormat::BC4 => (131, vec![Sample::block(0, 63, 0)]),
+        RenderFormat::BC5 => (132, vec![Sample::block(0, 63, 0), Sample::block(64, 63, 1)]),
+        RenderFormat::BC7 => (134, vec![Sample::block(0, 127, 0)]),
+        RenderFormat::R8G8B8A8 => (
+            1,
+            vec![
+                Sample::unorm8(0, 0),
+                Sample::unorm8(1, 1),
+                Sample::unorm8(2, 2),
+                Sample::unorm8(3, 15),
+            ],
+        ),
+        RenderFormat::R8G8 => (1, vec![Sample::unorm8(0, 0), Sample::unorm8(1, 1)]),
+        RenderFormat::A8 => (1, vec![Sample::unorm8(0, 15)]),
+        RenderFormat::R16G16B16A16 => (
+            1,
+            vec![
+                Sample::float16(0, 0),
+                Sample::float16(1, 1),
+                Sample::float16(2, 2),
+                Sample::float16(3, 15),
+            ],
+        ),
+    };
+
+    let block_dimensions = if format.is_compressed() { 3 } else { 0 };
+    let descriptor_block_size = 24 + 16 * samples.len();
+
+    let mut dfd = Vec::with_capacity(4 + descriptor_block_size);
+    dfd.extend(((4 + descriptor_block_size) as u32).to_le_bytes());
+    dfd.extend(0u32.to_le_bytes()); // vendor id and descriptor type
+    dfd.extend(2u16.to_le_bytes()); // version
+    dfd.extend((descriptor_block_size as u16).to_le_bytes());
+    dfd.extend([color_model, 1, 1, 0]); // BT.709 primaries, linear transfer, straight alpha
+    dfd.extend([block_dimensions, block_dimensions, 0, 0]);
+    dfd.extend([format.block_size() as u8, 0, 0, 0, 0, 0, 0, 0]);
+    for sample in samples {
+        dfd.extend(sample.bit_offset.to_le_bytes());
+        dfd.extend([sample.bit_length, sample.channel, 0, 0, 0, 0]);
+        dfd.extend(sample.lower.to_le_bytes());
+        dfd.extend(sample.upper.to_le_bytes());
+    }
+    dfd
+}
+
+/// Writes the mip levels, ordered from largest to smallest, to a KTX2 file.
+/// Every level holds `face_count` faces back to back.
+pub(crate) fn write_ktx2(
+    format: RenderFormat,
+    width: usize,
+    height: usize,
+    face_count: u32,
+    mips: &[Vec<u8>],
+) -> BinResult<Vec<u8>> {
+    let dfd = data_format_descriptor(format);
+    let dfd_byte_offset = HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE * mips.len();
+
+    // levels are aligned to the least common multiple of the block size and 4
+    let alignment = format.block_size().max(4);
+    let mut levels = vec![];
+    let mut data = vec![];
+    let mut offset = dfd_byte_offset + dfd.len();
+    // the smallest level is stored first
+    for mip in mips.iter().rev() {
+        let padding = offset.next_multiple_of(alignment) - offset;
+        data.resize(data.len() + padding, 0);
+        offset += padding;
+        levels.push(Ktx2Level {
+            byte_offset: offset as u64,
+            byte_length: mip.len() as u64,
+            uncompressed_byte_length: mip.len() as u64,
+        });
+        data.extend_from_slice(mip);
+        offset += mip.len();
+    }
+    levels.reverse();
+
+    let header = Ktx2Header {
+        vk_format: vk_format(format),
+        type_size: match format {
+            RenderFormat::R16G16B16A16 => 2,
+            _ => 1,
+        },
+        pixel_width: width as u32,
+        pixel_height: height as u32,
+        pixel_depth: 0,
+        layer_count: 0,
+        face_count,
+        level_count: mips.len() as u32,
+        supercompression_scheme: 0,
+        dfd_byte_offset: dfd_byte_offset as u32,
+        dfd_byte_length: dfd.len() as u32,
+        kvd_byte_offset: 0,
+        kvd_byte_length: 0,
+        sgd_byte_offset: 0,
+        sgd_byte_length: 0,
+        levels,
+    };
+
+    let mut writer = Cursor::new(vec![]);
+    header.write(&mut writer)?;
+    let mut ktx2 = writer.into_inner();
+    ktx2.extend(dfd);
+    ktx2.extend(data);
+    Ok(ktx2)
+}
+
+/// A 2D texture read from a KTX2 file.
+pub(crate) struct Ktx2Texture {
+    pub(crate) format: DXGI_FORMAT,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    /// The mip levels, ordered from largest to smallest.
+    pub(crate) mips: Vec<Vec<u8>>,
+    /// The file holds a single red channel, which is spread over every channel of the RGBA8 mips.
+    pub(crate) luminance: bool,
+}
+
+/// Reads a single 2D texture from a KTX2 file.
+/// Supercompressed files, cubemaps, arrays and volume textures are rejected.
+/// `VK_FORMAT_R8_UNORM` files are read as RGBA8 with the red value in every channel.
+pub(crate) fn read_ktx2(data: &[u8]) -> Result<Ktx2Texture, TexturePackerError> {
+    let header = Ktx2Header::read(&mut Cursor::new(data))?;
+
+    let luminance = header.vk_format == VK_FORMAT_R8_UNORM;
+    let format = if luminance {
+        DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM
+    } else {
+        dxgi_format(header.vk_format).ok_or(PackingError(format!(
+            "Unsupported KTX2 format: {}",
+            header.vk_format
+        )))?
+    };
+    if header.supercompression_scheme != 0 {
+        return Err(PackingError(format!(
+            "Supercompressed KTX2 files are not supported (scheme {})",
+            header.supercompression_scheme
+        )));
+    }
+    if header.face_count != 1 || header.layer_count > 1 || header.pixel_depth > 1 {
+        return Err(PackingError(format!(
+            "Only single 2D textures are supported, found {} faces, {} layers and a depth of {}",
+            header.face_count, header.layer_count, header.pixel_depth
+        )));
+    }
+    if header.level_count > MAX_LEVELS {
+        return Err(PackingError(format!(
+            "The KTX2 file holds {} mip levels",
+            header.level_count
+        )));
+    }
+    if header.pixel_width == 0 || header.pixel_height == 0 {
+        return Err(PackingError("The KTX2 file is empty".to_string()));
+    }
+
+    let mips = header
+        .levels
+        .iter()
+        .enumerate()
+        .map(|(level, index)| {
+            usize::try_from(index.byte_offset)
+                .ok()
+                .zip(usize::try_from(index.byte_length).ok())
+                .and_then(|(start, length)| data.get(start..start.checked_add(length)?))
+                .map(|mip| match luminance {
+                    true => mip.iter().flat_map(|value| [*value; 4]).collect(),
+                    false => mip.to_vec(),
+                })
+                .ok_or(PackingError(format!(
+                    "Mip level {level} lies outside of the file"
+                )))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Ktx2Texture {
+        format,
+        width: header.pixel_width as usize,
+        height: header.pixel_height as usize,
+        mips,
+        luminance,
+    })
+}
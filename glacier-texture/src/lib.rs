@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
 pub mod texture_map;
@@ -9,10 +10,20 @@ pub mod pack;
 pub mod enums;
 pub mod mipblock;
 pub mod atlas;
+pub mod analysis;
+pub mod scan;
+pub mod compare;
+pub mod mipgen;
+pub mod progress;
+pub mod height;
+pub mod header_info;
+pub(crate) mod ktx2;
+pub(crate) mod hash;
+pub(crate) mod tiling;
 #[cfg(feature = "image")]
 pub mod image;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub enum WoaVersion {
     HM2016,
     HM2,
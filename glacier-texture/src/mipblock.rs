@@ -5,7 +5,7 @@ use binrw::{BinRead, BinReaderExt};
 use serde::{Deserialize, Serialize};
 use crate::atlas::AtlasData;
 use crate::pack::TexturePackerError;
-use crate::texture_map::{TextureMapError, TextureMapHeaderImpl, TextureMapHeaderV1, TextureMapHeaderV2};
+use crate::texture_map::{first_difference, TextureMapError, TextureMapHeaderImpl, TextureMapHeaderV1, TextureMapHeaderV2};
 use crate::WoaVersion;
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MipblockData {
@@ -52,9 +52,11 @@ impl MipblockData{
                 stream.set_position(0);
                 stream.read_exact(&mut header).map_err(TextureMapError::IoError)?;
 
-                memory_reqs = (texd_header.mip_sizes.first().copied().unwrap_or(0x0) + texd_header.mip_sizes.get(1).copied().unwrap_or(0x0)) as usize;
+                memory_reqs = texd_header.mip_sizes.first().copied().unwrap_or(0x0) as usize + texd_header.mip_sizes.get(1).copied().unwrap_or(0x0) as usize;
 
-                data_size as usize - (TextureMapHeaderV1::size() - 8) - atlas.map(|a| a.size()).unwrap_or(0)
+                (data_size as usize)
+                    .checked_sub(TextureMapHeaderV1::size() - 8 + atlas.map(|a| a.size()).unwrap_or(0))
+                    .ok_or(TextureMapError::UnknownError(format!("the TEXD data size {data_size} is smaller than its header")))?
             }
             WoaVersion::HM2 => {
                 stream.set_position(4);
@@ -71,15 +73,21 @@ impl MipblockData{
                 stream.set_position(0);
                 stream.read_exact(&mut header).map_err(TextureMapError::IoError)?;
 
-                memory_reqs = (texd_header.mip_sizes.first().copied().unwrap_or(0x0) + texd_header.mip_sizes.get(1).copied().unwrap_or(0x0)) as usize;
+                memory_reqs = texd_header.mip_sizes.first().copied().unwrap_or(0x0) as usize + texd_header.mip_sizes.get(1).copied().unwrap_or(0x0) as usize;
 
-                data_size as usize - (TextureMapHeaderV2::size()) - atlas.map(|a| a.size()).unwrap_or(0)
+                (data_size as usize)
+                    .checked_sub(TextureMapHeaderV2::size() + atlas.map(|a| a.size()).unwrap_or(0))
+                    .ok_or(TextureMapError::UnknownError(format!("the TEXD data size {data_size} is smaller than its header")))?
             }
             WoaVersion::HM3 => {
                 data.len()
             }
         };
 
+        let remaining = data.len() - stream.position() as usize;
+        if read_size > remaining {
+            return Err(TextureMapError::UnknownError(format!("the TEXD claims to hold {read_size} bytes, but only {remaining} are left")));
+        }
         let mut buffer = vec![0u8; read_size];
         stream.read_exact(&mut buffer)?;
         Ok(Self{
@@ -106,6 +114,12 @@ impl MipblockData{
         Ok(())
     }
 
+    /// Packs the mipblock and compares it against the bytes it was read from.
+    /// Returns the offset of the first byte that differs, `None` if the output is identical.
+    pub fn verify_lossless(&self, original: &[u8], woa_version: WoaVersion) -> Result<Option<usize>, TexturePackerError> {
+        Ok(first_difference(&self.pack_to_vec(woa_version)?, original))
+    }
+
     fn pack_internal<W: Write + Seek>(&self, writer: &mut W, woa_version: WoaVersion) -> Result<(), TexturePackerError> {
         writer.write_all(match woa_version{
             WoaVersion::HM2016 |
@@ -0,0 +1,257 @@
+use crate::pack::MipFilter;
+
+/// Options for generating the mip chain in [`crate::pack::TextureMapBuilder::build`].
+/// When any option is enabled, levels are generated in 32-bit float precision with the
+/// [`MipFilter`] of the parameters, instead of by DirectXTex.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MipGenerationOptions {
+    /// Scales the alpha of every generated level so the fraction of pixels above this cutoff matches
+    /// the largest level. This keeps alpha-tested foliage and fences from fading out at a distance.
+    pub alpha_coverage_cutoff: Option<f32>,
+    /// Filters the colour channels in linear light instead of the sRGB encoded values.
+    pub linear_light: bool,
+    /// Weighs the colour channels by their alpha, so transparent pixels don't bleed into their neighbours.
+    pub premultiplied_alpha: bool,
+    /// Treats the colour channels as a tangent-space normal and normalizes every filtered vector.
+    /// For two-channel sources like R8G8 and BC5, Z is rebuilt from X and Y before filtering.
+    pub renormalize_normals: bool,
+}
+
+impl MipGenerationOptions {
+    pub(crate) fn is_enabled(&self) -> bool {
+        *self != Self::default()
+    }
+
+    fn encode_for_filtering(&self, pixel: [f32; 4], two_channel: bool) -> [f32; 4] {
+        let [mut r, mut g, mut b, a] = pixel;
+        if self.renormalize_normals {
+            [r, g, b] = [r, g, b].map(|c| c * 2.0 - 1.0);
+            if two_channel {
+                b = (1.0 - r * r - g * g).max(0.0).sqrt();
+            }
+        } else if self.linear_light {
+            [r, g, b] = [r, g, b].map(srgb_to_linear);
+        }
+        if self.premultiplied_alpha {
+            [r, g, b] = [r, g, b].map(|c| c * a);
+        }
+        [r, g, b, a]
+    }
+
+    fn decode_after_filtering(&self, pixel: [f32; 4], unorm: bool) -> [f32; 4] {
+        let [mut r, mut g, mut b, a] = pixel;
+        if self.premultiplied_alpha && a > 0.0 {
+            [r, g, b] = [r, g, b].map(|c| c / a);
+        }
+        if self.renormalize_normals {
+            let length = (r * r + g * g + b * b).sqrt();
+            [r, g, b] = if length > f32::EPSILON {
+                [r, g, b].map(|c| c / length)
+            } else {
+                [0.0, 0.0, 1.0]
+            };
+            [r, g, b] = [r, g, b].map(|c| c * 0.5 + 0.5);
+        } else if self.linear_light {
+            [r, g, b] = [r, g, b].map(linear_to_srgb);
+        }
+        if unorm {
+            [r, g, b, a].map(|c| c.clamp(0.0, 1.0))
+        } else {
+            [r, g, b, a]
+        }
+    }
+}
+
+/// A single mip level with RGBA values, in the 0-1 range unless the target format is a float format.
+pub(crate) struct MipLevelF32 {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) pixels: Vec<[f32; 4]>,
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// The Catmull-Rom spline, which DirectXTex uses for its cubic filter.
+fn cubic(t: f32) -> f32 {
+    let t = t.abs();
+    if t < 1.0 {
+        1.5 * t * t * t - 2.5 * t * t + 1.0
+    } else if t < 2.0 {
+        -0.5 * t * t * t + 2.5 * t * t - 4.0 * t + 2.0
+    } else {
+        0.0
+    }
+}
+
+/// The source texels, with their weights, that make up each texel when scaling a row of `from` texels to `to`.
+/// Texels beyond the edge are clamped to the edge, the weights of every texel add up to 1.
+fn filter_weights(filter: MipFilter, from: usize, to: usize) -> Vec<Vec<(usize, f32)>> {
+    let scale = from as f32 / to as f32;
+    (0..to)
+        .map(|x| {
+            let start = x as f32 * scale;
+            let end = start + scale;
+            let mut weights = match filter {
+                MipFilter::Nearest => vec![((start as usize).min(from - 1), 1.0)],
+                // the exact area every source texel covers, this includes the edge texels of odd sizes
+                MipFilter::Box => (start.floor() as usize..end.ceil() as usize)
+                    .map(|i| {
+                        let covered = end.min(i as f32 + 1.0) - start.max(i as f32);
+                        (i.min(from - 1), covered.max(0.0))
+                    })
+                    .collect(),
+                MipFilter::Linear | MipFilter::Cubic => {
+                    let (kernel, radius): (fn(f32) -> f32, f32) = match filter {
+                        MipFilter::Cubic => (cubic, 2.0),
+                        _ => (|t: f32| (1.0 - t.abs()).max(0.0), 1.0),
+                    };
+                    let center = (start + end) * 0.5;
+                    let width = scale.max(1.0);
+                    let first = (center - radius * width).floor() as isize;
+                    let last = (center + radius * width).ceil() as isize;
+                    (first..=last)
+                        .map(|i| {
+                            let t = (i as f32 + 0.5 - center) / width;
+                            (i.clamp(0, from as isize - 1) as usize, kernel(t))
+                        })
+                        .collect()
+                }
+            };
+            weights.retain(|(_, weight)| *weight != 0.0);
+            let total = weights.iter().map(|(_, weight)| weight).sum::<f32>();
+            weights.iter_mut().for_each(|(_, weight)| *weight /= total);
+            weights
+        })
+        .collect()
+}
+
+/// Halves the size of a level with `filter`, the rows and columns are filtered separately.
+fn downsample(level: &MipLevelF32, filter: MipFilter) -> MipLevelF32 {
+    let width = (level.width / 2).max(1);
+    let height = (level.height / 2).max(1);
+    let columns = filter_weights(filter, level.width, width);
+    let rows = filter_weights(filter, level.height, height);
+
+    let weighted_sum = |texels: &mut dyn Iterator<Item = ([f32; 4], f32)>| {
+        texels.fold([0f32; 4], |mut sum, (pixel, weight)| {
+            sum.iter_mut()
+                .zip(pixel)
+                .for_each(|(s, c)| *s += c * weight);
+            sum
+        })
+    };
+
+    let mut horizontal = Vec::with_capacity(width * level.height);
+    for y in 0..level.height {
+        let row = &level.pixels[y * level.width..(y + 1) * level.width];
+        for weights in &columns {
+            horizontal.push(weighted_sum(
+                &mut weights.iter().map(|(x, w)| (row[*x], *w)),
+            ));
+        }
+    }
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for weights in &rows {
+        for x in 0..width {
+            pixels.push(weighted_sum(
+                &mut weights.iter().map(|(y, w)| (horizontal[y * width + x], *w)),
+            ));
+        }
+    }
+    MipLevelF32 {
+        width,
+        height,
+        pixels,
+    }
+}
+
+fn alpha_coverage(pixels: &[[f32; 4]], cutoff: f32, scale: f32) -> f32 {
+    let covered = pixels
+        .iter()
+        .filter(|pixel| pixel[3] * scale > cutoff)
+        .count();
+    covered as f32 / pixels.len().max(1) as f32
+}
+
+/// Scales the alpha channel so its coverage at `cutoff` matches `target`.
+fn preserve_alpha_coverage(level: &mut MipLevelF32, cutoff: f32, target: f32, unorm: bool) {
+    let (mut low, mut high) = (0.0f32, 4.0f32);
+    for _ in 0..16 {
+        let mid = (low + high) * 0.5;
+        if alpha_coverage(&level.pixels, cutoff, mid) < target {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    let scale = (low + high) * 0.5;
+    level.pixels.iter_mut().for_each(|pixel| {
+        pixel[3] *= scale;
+        if unorm {
+            pixel[3] = pixel[3].clamp(0.0, 1.0);
+        }
+    });
+}
+
+/// Generates `levels` mip levels, including the given base level.
+/// `two_channel` marks sources that only store red and green, like R8G8 and BC5.
+/// `unorm` clamps the generated levels to the 0-1 range, float targets keep values outside of it.
+/// `before_level` is called with the index of every level before it is generated, an error stops the generation.
+pub(crate) fn generate_mip_chain<E>(
+    base: MipLevelF32,
+    levels: usize,
+    options: &MipGenerationOptions,
+    filter: MipFilter,
+    two_channel: bool,
+    unorm: bool,
+    mut before_level: impl FnMut(usize) -> Result<(), E>,
+) -> Result<Vec<MipLevelF32>, E> {
+    let target_coverage = options
+        .alpha_coverage_cutoff
+        .map(|cutoff| (cutoff, alpha_coverage(&base.pixels, cutoff, 1.0)));
+
+    let mut filtered = MipLevelF32 {
+        width: base.width,
+        height: base.height,
+        pixels: base
+            .pixels
+            .iter()
+            .map(|p| options.encode_for_filtering(*p, two_channel))
+            .collect(),
+    };
+
+    let mut chain = vec![base];
+    for level in 1..levels {
+        before_level(level)?;
+        filtered = downsample(&filtered, filter);
+        let mut level = MipLevelF32 {
+            width: filtered.width,
+            height: filtered.height,
+            pixels: filtered
+                .pixels
+                .iter()
+                .map(|p| options.decode_after_filtering(*p, unorm))
+                .collect(),
+        };
+        if let Some((cutoff, target)) = target_coverage {
+            preserve_alpha_coverage(&mut level, cutoff, target, unorm);
+        }
+        chain.push(level);
+    }
+    Ok(chain)
+}
@@ -1,15 +1,19 @@
+use crate::analysis::FormatAnalysis;
 use crate::atlas::AtlasData;
 use crate::enums::*;
 use crate::mipblock::MipblockData;
+use crate::mipgen::{MipGenerationOptions, MipLevelF32};
 use crate::pack::TexturePackerError::{DirectXTexError, PackingError};
+use crate::progress::{CancellationToken, Cancelled, Progress, ProgressHooks, Stage};
 use crate::texture_map::{
-    TextureData, TextureMap, TextureMapHeaderV1, TextureMapHeaderV2, TextureMapHeaderV3,
-    TextureMapInner,
+    TextureData, TextureMap, TextureMapError, TextureMapHeaderV1, TextureMapHeaderV2,
+    TextureMapHeaderV3, TextureMapInner, MAX_MIP_LEVELS, SCALED_TEXD_IDENTIFIER,
 };
-use crate::{convert, WoaVersion};
+use crate::tiling::{self, Tiling};
+use crate::{analysis, convert, height, ktx2, mipgen, WoaVersion};
 use directxtex::{
-    Image, ScratchImage, DDS_FLAGS, DXGI_FORMAT, TEX_COMPRESS_FLAGS, TEX_FILTER_FLAGS,
-    TEX_THRESHOLD_DEFAULT, TGA_FLAGS,
+    Image, ScratchImage, TexMetadata, CP_FLAGS, DDS_FLAGS, DXGI_FORMAT, TEX_COMPRESS_FLAGS,
+    TEX_DIMENSION, TEX_FILTER_FLAGS, TEX_THRESHOLD_DEFAULT, TGA_FLAGS,
 };
 #[cfg(feature = "image")]
 use image::DynamicImage;
@@ -17,6 +21,8 @@ use lz4::block::CompressionMode;
 use std::cmp::max;
 use std::io::{Cursor, Read};
 use std::ptr::NonNull;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{io, slice};
 use thiserror::Error;
 
@@ -33,6 +39,15 @@ pub enum TexturePackerError {
 
     #[error("Error building texture: {0}")]
     PackingError(String),
+
+    #[error("Failed to read the source texture: {0}")]
+    TextureMapError(#[from] TextureMapError),
+
+    #[error("The texture stores {0} mip level(s) in its TEXD, which is not loaded")]
+    MissingTexd(usize),
+
+    #[error("The build was cancelled")]
+    Cancelled(#[from] Cancelled),
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -49,6 +64,95 @@ pub enum MipFilter {
     Box,
 }
 
+impl FromStr for MipFilter {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nearest" | "point" => Ok(MipFilter::Nearest),
+            "linear" => Ok(MipFilter::Linear),
+            "cubic" => Ok(MipFilter::Cubic),
+            "box" => Ok(MipFilter::Box),
+            _ => Err(format!("Invalid value for MipFilter: {s}")),
+        }
+    }
+}
+
+impl From<MipFilter> for TEX_FILTER_FLAGS {
+    fn from(value: MipFilter) -> Self {
+        match value {
+            MipFilter::Nearest => TEX_FILTER_FLAGS::TEX_FILTER_POINT,
+            MipFilter::Linear => TEX_FILTER_FLAGS::TEX_FILTER_LINEAR,
+            MipFilter::Cubic => TEX_FILTER_FLAGS::TEX_FILTER_CUBIC,
+            MipFilter::Box => TEX_FILTER_FLAGS::TEX_FILTER_BOX,
+        }
+    }
+}
+
+/// Channel layout of a tightly packed pixel buffer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PixelLayout {
+    Rgba8,
+    Rg8,
+    /// A single 8-bit channel, it can be encoded to both [`RenderFormat::A8`] and [`RenderFormat::BC4`].
+    R8,
+    /// Half precision floats, passed as their raw bits.
+    Rgba16F,
+    Rgba32F,
+}
+
+impl PixelLayout {
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelLayout::Rgba8 => 4,
+            PixelLayout::Rg8 => 2,
+            PixelLayout::R8 => 1,
+            PixelLayout::Rgba16F => 8,
+            PixelLayout::Rgba32F => 16,
+        }
+    }
+}
+
+/// The input of a single channel when packing a mask texture with [`TextureMapBuilder::from_channels`].
+#[derive(Clone, Debug)]
+pub enum ChannelSource {
+    /// A grayscale image, given as tightly packed 8-bit values.
+    Grayscale(Vec<u8>),
+    /// Fills the whole channel with a single value.
+    Constant(u8),
+}
+
+/// Named parameter sets that follow the conventions of shipped game textures.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TexturePreset {
+    Diffuse,
+    Normal,
+    CompoundNormal,
+    Height,
+    Emission,
+    Billboard,
+    Mask,
+    UI,
+}
+
+impl FromStr for TexturePreset {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "diffuse" | "colour" | "color" => Ok(TexturePreset::Diffuse),
+            "normal" => Ok(TexturePreset::Normal),
+            "compoundnormal" | "compound_normal" | "compound-normal" => {
+                Ok(TexturePreset::CompoundNormal)
+            }
+            "height" => Ok(TexturePreset::Height),
+            "emission" | "emissive" => Ok(TexturePreset::Emission),
+            "billboard" => Ok(TexturePreset::Billboard),
+            "mask" => Ok(TexturePreset::Mask),
+            "ui" => Ok(TexturePreset::UI),
+            _ => Err(format!("Invalid value for TexturePreset: {s}")),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct TextureMapParameters {
     texture_type: TextureType,
@@ -60,6 +164,7 @@ pub struct TextureMapParameters {
     default_mip_level: u8,
     texd_identifier: u32,
     mip_filter: MipFilter,
+    mip_generation: MipGenerationOptions,
 }
 
 impl TextureMapParameters {
@@ -73,8 +178,9 @@ impl TextureMapParameters {
             format,
             num_mip_levels: MipLevels::All,
             default_mip_level: 0,
-            texd_identifier: 0x4000,
+            texd_identifier: SCALED_TEXD_IDENTIFIER,
             mip_filter: MipFilter::Box,
+            mip_generation: MipGenerationOptions::default(),
         }
     }
 
@@ -88,8 +194,81 @@ impl TextureMapParameters {
             format: texture.format(),
             num_mip_levels: MipLevels::All,
             default_mip_level: 0,
-            texd_identifier: 0x4000,
+            texd_identifier: SCALED_TEXD_IDENTIFIER,
             mip_filter: MipFilter::Box,
+            mip_generation: MipGenerationOptions::default(),
+        }
+    }
+
+    /// Creates parameters matching the way the game ships textures of the given kind.
+    pub fn from_preset(preset: TexturePreset, woa_version: WoaVersion) -> Self {
+        // HM3 ships most colour data as BC7, the older games mostly use BC1 and BC3
+        let colour_format = match woa_version {
+            WoaVersion::HM3 => RenderFormat::BC7,
+            WoaVersion::HM2016 | WoaVersion::HM2 => RenderFormat::BC1,
+        };
+        let alpha_format = match woa_version {
+            WoaVersion::HM3 => RenderFormat::BC7,
+            WoaVersion::HM2016 | WoaVersion::HM2 => RenderFormat::BC3,
+        };
+
+        let (texture_type, interpret_as, format) = match preset {
+            TexturePreset::Diffuse => (TextureType::Colour, InterpretAs::Colour, colour_format),
+            TexturePreset::Normal => (TextureType::Normal, InterpretAs::Normal, RenderFormat::BC5),
+            TexturePreset::CompoundNormal => (
+                TextureType::CompoundNormal,
+                InterpretAs::CompoundNormal,
+                RenderFormat::BC5,
+            ),
+            TexturePreset::Height => (TextureType::Height, InterpretAs::Height, RenderFormat::BC4),
+            TexturePreset::Emission => {
+                (TextureType::Emission, InterpretAs::Emission, colour_format)
+            }
+            TexturePreset::Billboard => {
+                (TextureType::Billboard, InterpretAs::Billboard, alpha_format)
+            }
+            TexturePreset::Mask => (TextureType::Colour, InterpretAs::Colour, alpha_format),
+            TexturePreset::UI => (
+                TextureType::Colour,
+                InterpretAs::Colour,
+                RenderFormat::R8G8B8A8,
+            ),
+        };
+
+        // unknown1 is only found on compressed colour and normal textures
+        let unknown1 = matches!(
+            preset,
+            TexturePreset::Diffuse | TexturePreset::Normal | TexturePreset::CompoundNormal
+        );
+
+        let num_mip_levels = match preset {
+            TexturePreset::UI => MipLevels::Limit(1),
+            _ => MipLevels::All,
+        };
+
+        Self {
+            texture_type,
+            interpret_as,
+            flags: TextureFlagsInner::default()
+                .with_unknown1(unknown1)
+                .with_unknown3(true),
+            num_mip_levels,
+            default_mip_level: match woa_version {
+                WoaVersion::HM2 => 1, //H2 crashes with index 0
+                WoaVersion::HM2016 | WoaVersion::HM3 => 0,
+            },
+            ..Self::new(format)
+        }
+    }
+
+    /// Captures every header value of an existing texture.
+    /// Unlike [`Self::from_texture_map`], the default mip level, dimensions and TEXD identifier are kept.
+    pub(crate) fn from_texture_map_header(texture: &TextureMap) -> Self {
+        Self {
+            dimensions: texture.dimensions(),
+            default_mip_level: texture.default_mip_level(),
+            texd_identifier: texture.texd_identifier(),
+            ..Self::from_texture_map(texture)
         }
     }
 
@@ -123,6 +302,10 @@ impl TextureMapParameters {
         self.mip_filter
     }
 
+    pub fn mip_generation(&self) -> MipGenerationOptions {
+        self.mip_generation
+    }
+
     pub fn set_texture_type(&mut self, texture_type: TextureType) {
         self.texture_type = texture_type;
     }
@@ -160,6 +343,10 @@ impl TextureMapParameters {
     pub fn set_mip_filter(&mut self, mip_filter: MipFilter) {
         self.mip_filter = mip_filter;
     }
+
+    pub fn set_mip_generation(&mut self, mip_generation: MipGenerationOptions) {
+        self.mip_generation = mip_generation;
+    }
 }
 
 /// Builder struct for constructing TextureMap instances.
@@ -169,6 +356,11 @@ pub struct TextureMapBuilder {
     atlas_data: Option<AtlasData>,
     image: ScratchImage,
     use_mipblock1: bool,
+    text_only: bool,
+    auto_format: bool,
+    keep_authored_mips: bool,
+    normal_from_height: Option<f32>,
+    hooks: ProgressHooks,
 }
 
 impl TextureMapBuilder {
@@ -188,6 +380,74 @@ impl TextureMapBuilder {
         Self::from_scratch_image(image)
     }
 
+    /// Creates a builder from a list of DDS images, one per mip level starting at the largest.
+    /// Every level needs to be half the size of the previous one and share its format.
+    /// The levels are kept as authored during [`Self::build`], only the missing smaller levels are generated.
+    pub fn from_dds_levels<R: Read>(
+        readers: impl IntoIterator<Item = R>,
+    ) -> Result<Self, TexturePackerError> {
+        let levels = readers
+            .into_iter()
+            .map(|reader| Self::from_dds(reader).map(|builder| builder.image))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let base = levels
+            .first()
+            .ok_or(PackingError("No mip levels were provided".to_string()))?
+            .metadata();
+        let (format, width, height) = (base.format, base.width, base.height);
+
+        let mut mips = vec![];
+        for (level, image) in levels.iter().enumerate() {
+            let metadata = image.metadata();
+            let expected = ((width >> level).max(1), (height >> level).max(1));
+            if (metadata.width, metadata.height) != expected {
+                return Err(PackingError(format!(
+                    "Mip level {level} is {}x{}, expected {}x{}",
+                    metadata.width, metadata.height, expected.0, expected.1
+                )));
+            }
+            if metadata.format != format {
+                return Err(PackingError(format!(
+                    "Mip level {level} uses {:?}, expected {:?}",
+                    metadata.format, format
+                )));
+            }
+            mips.push(
+                image
+                    .image(0, 0, 0)
+                    .and_then(Self::process_mip_image)
+                    .ok_or(PackingError(format!("Mip level {level} is empty")))?,
+            );
+        }
+
+        let image = scratch_image_from_mips(format, width, height, &mut mips)?;
+        Ok(Self::from_scratch_image(image)?.with_authored_mips(true))
+    }
+
+    /// Creates a builder from a KTX2 file, the mip levels it contains are kept as authored.
+    /// Block compressed data is copied as-is when the format is left unchanged.
+    /// Only single 2D textures without supercompression are supported.
+    /// Single channel `R8` files are read like [`Self::from_r8`].
+    pub fn from_ktx2<R: Read>(mut reader: R) -> Result<Self, TexturePackerError> {
+        let mut data = vec![];
+        reader
+            .read_to_end(&mut data)
+            .map_err(TexturePackerError::IoError)?;
+        let mut texture = ktx2::read_ktx2(&data)?;
+        let image = scratch_image_from_mips(
+            texture.format,
+            texture.width,
+            texture.height,
+            &mut texture.mips,
+        )?;
+        let mut builder = Self::from_scratch_image(image)?.with_authored_mips(true);
+        if texture.luminance {
+            builder.params.format = RenderFormat::A8;
+        }
+        Ok(builder)
+    }
+
     pub fn from_tga<R: Read>(mut reader: R) -> Result<Self, TexturePackerError> {
         let mut image_data = vec![];
         reader
@@ -198,6 +458,118 @@ impl TextureMapBuilder {
         Self::from_scratch_image(image)
     }
 
+    /// Creates a builder from a tightly packed pixel buffer, the rows are stored from top to bottom.
+    pub fn from_pixels(
+        width: u32,
+        height: u32,
+        layout: PixelLayout,
+        pixels: &[u8],
+    ) -> Result<Self, TexturePackerError> {
+        let (width, height) = (width as usize, height as usize);
+        let expected_size = width * height * layout.bytes_per_pixel();
+        if pixels.len() != expected_size {
+            return Err(PackingError(format!(
+                "Expected {expected_size} bytes for a {width}x{height} {layout:?} image, got {}",
+                pixels.len()
+            )));
+        }
+
+        let (dxgi_format, data) = match layout {
+            PixelLayout::Rgba8 => (DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM, pixels.to_vec()),
+            PixelLayout::Rg8 => (DXGI_FORMAT::DXGI_FORMAT_R8G8_UNORM, pixels.to_vec()),
+            // A8 reads the alpha channel while BC4 reads red, so the value is spread over every channel.
+            PixelLayout::R8 => (
+                DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM,
+                pixels.iter().flat_map(|value| [*value; 4]).collect(),
+            ),
+            PixelLayout::Rgba16F => (DXGI_FORMAT::DXGI_FORMAT_R16G16B16A16_FLOAT, pixels.to_vec()),
+            PixelLayout::Rgba32F => (DXGI_FORMAT::DXGI_FORMAT_R32G32B32A32_FLOAT, pixels.to_vec()),
+        };
+
+        let image = scratch_image_from_mips(dxgi_format, width, height, &mut [data])?;
+        let mut builder = Self::from_scratch_image(image)?;
+        if layout == PixelLayout::R8 {
+            builder.params.format = RenderFormat::A8;
+        }
+        Ok(builder)
+    }
+
+    /// Creates an RGBA8 builder by packing a grayscale source or a constant into each channel.
+    /// The sources are given in red, green, blue, alpha order.
+    pub fn from_channels(
+        width: u32,
+        height: u32,
+        channels: [ChannelSource; 4],
+    ) -> Result<Self, TexturePackerError> {
+        let pixel_count = width as usize * height as usize;
+        for (channel, source) in [Channel::Red, Channel::Green, Channel::Blue, Channel::Alpha]
+            .iter()
+            .zip(&channels)
+        {
+            if let ChannelSource::Grayscale(values) = source {
+                if values.len() != pixel_count {
+                    return Err(PackingError(format!(
+                        "Expected {pixel_count} bytes for the {channel:?} channel of a {width}x{height} image, got {}",
+                        values.len()
+                    )));
+                }
+            }
+        }
+
+        let mut pixels = vec![0u8; pixel_count * 4];
+        for (index, source) in channels.iter().enumerate() {
+            let values = pixels.iter_mut().skip(index).step_by(4);
+            match source {
+                ChannelSource::Grayscale(source) => values
+                    .zip(source)
+                    .for_each(|(value, source)| *value = *source),
+                ChannelSource::Constant(constant) => values.for_each(|value| *value = *constant),
+            }
+        }
+        Self::from_rgba8(width, height, &pixels)
+    }
+
+    /// Creates a builder from 8-bit RGBA pixels.
+    pub fn from_rgba8(width: u32, height: u32, pixels: &[u8]) -> Result<Self, TexturePackerError> {
+        Self::from_pixels(width, height, PixelLayout::Rgba8, pixels)
+    }
+
+    /// Creates a builder from 8-bit RG pixels.
+    pub fn from_rg8(width: u32, height: u32, pixels: &[u8]) -> Result<Self, TexturePackerError> {
+        Self::from_pixels(width, height, PixelLayout::Rg8, pixels)
+    }
+
+    /// Creates a builder from single channel 8-bit pixels.
+    pub fn from_r8(width: u32, height: u32, pixels: &[u8]) -> Result<Self, TexturePackerError> {
+        Self::from_pixels(width, height, PixelLayout::R8, pixels)
+    }
+
+    /// Creates a builder from half precision RGBA pixels, given as their raw bits.
+    pub fn from_rgba16f(
+        width: u32,
+        height: u32,
+        pixels: &[u16],
+    ) -> Result<Self, TexturePackerError> {
+        let bytes = pixels
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        Self::from_pixels(width, height, PixelLayout::Rgba16F, &bytes)
+    }
+
+    /// Creates a builder from single precision RGBA pixels.
+    pub fn from_rgba32f(
+        width: u32,
+        height: u32,
+        pixels: &[f32],
+    ) -> Result<Self, TexturePackerError> {
+        let bytes = pixels
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        Self::from_pixels(width, height, PixelLayout::Rgba32F, &bytes)
+    }
+
     #[cfg(feature = "image")]
     pub fn from_dynamic_image(image: DynamicImage) -> Result<Self, TexturePackerError> {
         let scratch_image = crate::image::dynamic_image_to_scratch_image(
@@ -223,6 +595,7 @@ impl TextureMapBuilder {
                 (24, 8) => Ok(RenderFormat::R8G8B8A8),
                 (32, 8) => Ok(RenderFormat::R8G8B8A8),
                 (64, 16) => Ok(RenderFormat::R16G16B16A16),
+                (128, 32) => Ok(RenderFormat::R16G16B16A16),
                 _ => Err(PackingError(format!(
                     "Unsupported render format: bpp={}, bpc={}, channels={:?}, format={:?}",
                     bits_per_pixel, bits_per_color, num_channels, metadata.format
@@ -235,6 +608,11 @@ impl TextureMapBuilder {
             atlas_data: None,
             image,
             use_mipblock1: true,
+            text_only: false,
+            auto_format: false,
+            keep_authored_mips: false,
+            normal_from_height: None,
+            hooks: ProgressHooks::default(),
         })
     }
 
@@ -254,6 +632,90 @@ impl TextureMapBuilder {
         Ok(builder)
     }
 
+    /// Creates a builder from a single mip level of an existing texture, keeping all of its header values.
+    /// The level is decoded, so the source image can be filtered, resized and encoded to any format.
+    pub(crate) fn from_texture_map_mip(
+        texture: &TextureMap,
+        level: usize,
+    ) -> Result<Self, TexturePackerError> {
+        let image = Self::decode_mip_level(texture, level)?;
+        let mut builder = Self::from_scratch_image(image)?;
+        builder.params = TextureMapParameters::from_texture_map_header(texture);
+        builder.atlas_data = texture.atlas().clone();
+        builder.use_mipblock1 = texture.has_mipblock1();
+        Ok(builder)
+    }
+
+    /// Decodes a single mip level to an uncompressed image.
+    /// Single channel formats are spread over every channel, A8 stores its value in alpha while BC4 uses red.
+    fn decode_mip_level(
+        texture: &TextureMap,
+        level: usize,
+    ) -> Result<ScratchImage, TexturePackerError> {
+        let dds = convert::create_mip_dds(texture, level, false)
+            .map_err(|e| PackingError(format!("Failed to convert texture: {e}")))?;
+        let image = ScratchImage::load_dds(
+            dds.as_slice(),
+            DDS_FLAGS::DDS_FLAGS_FORCE_DX10_EXT,
+            None,
+            None,
+        )
+        .map_err(DirectXTexError)?;
+
+        let format = texture.format();
+        if format == RenderFormat::R16G16B16A16 || format == RenderFormat::R8G8B8A8 {
+            return Ok(image);
+        }
+
+        let mut image = if format.is_compressed() {
+            directxtex::decompress(
+                image.images(),
+                image.metadata(),
+                DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM,
+            )
+        } else {
+            directxtex::convert(
+                image.images(),
+                image.metadata(),
+                DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM,
+                TEX_FILTER_FLAGS::TEX_FILTER_DEFAULT | TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC,
+                TEX_THRESHOLD_DEFAULT,
+            )
+        }
+        .map_err(DirectXTexError)?;
+
+        if format.num_channels() == 1 {
+            let channel = if format == RenderFormat::A8 { 3 } else { 0 };
+            for pixel in image.pixels_mut().chunks_exact_mut(4) {
+                let value = pixel[channel];
+                pixel.fill(value);
+            }
+        }
+        Ok(image)
+    }
+
+    /// Resizes the source image using the configured mip filter.
+    pub(crate) fn resize_source(
+        mut self,
+        width: usize,
+        height: usize,
+    ) -> Result<Self, TexturePackerError> {
+        let filter = TEX_FILTER_FLAGS::from(self.params.mip_filter)
+            | TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC;
+        self.image = self
+            .image
+            .image(0, 0, 0)
+            .ok_or(PackingError("The source image is empty".to_string()))?
+            .resize(width, height, filter)
+            .map_err(DirectXTexError)?;
+        Ok(self)
+    }
+
+    /// The parameters the texture will be built with.
+    pub fn params(&self) -> &TextureMapParameters {
+        &self.params
+    }
+
     pub fn with_params(mut self, params: TextureMapParameters) -> Self {
         self.params = params;
         self
@@ -295,17 +757,141 @@ impl TextureMapBuilder {
         self
     }
 
+    pub fn with_mip_generation(mut self, mip_generation: MipGenerationOptions) -> Self {
+        self.params.set_mip_generation(mip_generation);
+        self
+    }
+
     pub fn with_atlas(mut self, atlas_data: AtlasData) -> Self {
         self.atlas_data = Some(atlas_data);
         self.params.flags = self.params.flags.with_atlas(true);
         self
     }
 
+    /// Splits the mips over a TEXT and a TEXD. Enabled by default.
+    /// When disabled, the full mip chain is stored in the TEXT.
     pub fn with_mipblock1(mut self, enabled: bool) -> Self {
         self.use_mipblock1 = enabled;
         self
     }
 
+    /// Builds the low resolution variant that ships without a TEXD, see [`TextureMap::text_only`].
+    /// The TEXT only holds the mips the header assigns to it, so a 1024x1024 HM3 texture is stored as 128x128.
+    /// This takes precedence over [`Self::with_mipblock1`].
+    pub fn with_text_only(mut self, enabled: bool) -> Self {
+        self.text_only = enabled;
+        self
+    }
+
+    /// When enabled, the mip levels that are present in the source image are kept instead of regenerated.
+    /// Only the levels missing from the source are generated.
+    pub fn with_authored_mips(mut self, enabled: bool) -> Self {
+        self.keep_authored_mips = enabled;
+        self
+    }
+
+    /// When enabled, the format is chosen by [`Self::analyze_format`] during [`Self::build`].
+    /// This overrides the format in the parameters.
+    pub fn with_auto_format(mut self, enabled: bool) -> Self {
+        self.auto_format = enabled;
+        self
+    }
+
+    /// Treats the source image as a height map and builds a tangent-space BC5 normal map from it.
+    /// `strength` scales the slopes, higher values give more pronounced details.
+    /// The height is read from alpha for A8 sources and from red for every other source.
+    pub fn with_normal_from_height(mut self, strength: f32) -> Self {
+        self.normal_from_height = Some(strength);
+        self
+    }
+
+    /// Calls `callback` as the build moves through mip generation, encoding and LZ4 compression.
+    pub fn with_progress(mut self, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.hooks = self.hooks.with_callback(callback);
+        self
+    }
+
+    /// Stops the build at the next stage or mip level once `token` is cancelled.
+    /// The build then fails with [`TexturePackerError::Cancelled`].
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.hooks = self.hooks.with_cancellation(token);
+        self
+    }
+
+    pub fn with_progress_hooks(mut self, hooks: ProgressHooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Replaces the source image with the normal map derived from its heights.
+    fn derive_normal_map(mut self, strength: f32) -> Result<Self, TexturePackerError> {
+        let channel = height::height_channel(self.image.metadata().format);
+        let (width, height, pixels) =
+            height::decode_rgba32f(&self.image).map_err(DirectXTexError)?;
+        if pixels.len() != width * height {
+            return Err(PackingError(
+                "The height source decoded to fewer pixels than expected".to_string(),
+            ));
+        }
+        let field = height::HeightField {
+            width,
+            height,
+            values: pixels.iter().map(|pixel| pixel[channel.index()]).collect(),
+        };
+        let normals = height::normal_map_from_height(&field, strength);
+
+        self.image = scratch_image_from_mips(
+            DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM,
+            width,
+            height,
+            &mut [normals],
+        )?;
+        self.keep_authored_mips = false;
+        self.auto_format = false;
+        self.params.format = RenderFormat::BC5;
+        self.params.texture_type = TextureType::Normal;
+        self.params.interpret_as = InterpretAs::Normal;
+        self.params.mip_generation.renormalize_normals = true;
+        Ok(self)
+    }
+
+    /// Inspects the pixels of the source image and proposes a format for the configured texture type.
+    pub fn analyze_format(&self) -> Result<FormatAnalysis, TexturePackerError> {
+        let format = self.image.metadata().format;
+        let converted = if format == DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM {
+            None
+        } else if format.is_compressed() {
+            Some(
+                directxtex::decompress(
+                    self.image.images(),
+                    self.image.metadata(),
+                    DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM,
+                )
+                .map_err(DirectXTexError)?,
+            )
+        } else {
+            Some(
+                directxtex::convert(
+                    self.image.images(),
+                    self.image.metadata(),
+                    DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM,
+                    TEX_FILTER_FLAGS::TEX_FILTER_DEFAULT
+                        | TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC,
+                    TEX_THRESHOLD_DEFAULT,
+                )
+                .map_err(DirectXTexError)?,
+            )
+        };
+
+        let pixels = converted
+            .as_ref()
+            .unwrap_or(&self.image)
+            .image(0, 0, 0)
+            .and_then(Self::process_mip_image)
+            .ok_or(PackingError("The source image is empty".to_string()))?;
+        Ok(analysis::analyze_rgba8(&pixels, self.params.texture_type))
+    }
+
     pub fn with_flags(mut self, flags: TextureFlags) -> Self {
         self.params.set_flags(flags);
         self
@@ -319,14 +905,14 @@ impl TextureMapBuilder {
 
     #[cfg(feature = "unstable")]
     pub fn with_texd_id(mut self, texd_id: u32) -> Self {
-        self.params.set_texd_id(texd_id);
+        self.params.set_texd_identifier(texd_id);
         self
     }
 
     ///Convert the image to a different format.
     /// It is assumed that the input image is not compressed
     fn convert_to_format(
-        image: ScratchImage,
+        image: &Image,
         new_format: DXGI_FORMAT,
     ) -> Result<ScratchImage, TexturePackerError> {
         let reqs = [
@@ -356,8 +942,134 @@ impl TextureMapBuilder {
         })
     }
 
+    /// [`Self::convert_to_format`] for one mip level at a time, every level is reported to the progress hooks
+    /// and a cancellation stops the encoding before the next level.
+    fn encode_to_format(
+        &self,
+        image: &ScratchImage,
+        new_format: DXGI_FORMAT,
+    ) -> Result<ScratchImage, TexturePackerError> {
+        let metadata = image.metadata();
+        let levels = metadata.mip_levels;
+        let mut mips = Vec::with_capacity(levels);
+        for level in 0..levels {
+            self.hooks.report(Stage::Encode, level, levels)?;
+            let mip = image
+                .image(level, 0, 0)
+                .ok_or(PackingError(format!("Missing mip level {level}")))?;
+            let encoded = Self::convert_to_format(mip, new_format)?;
+            mips.push(
+                encoded
+                    .image(0, 0, 0)
+                    .and_then(Self::process_mip_image)
+                    .ok_or(PackingError(format!("Failed to encode mip level {level}")))?,
+            );
+        }
+        self.hooks.report(Stage::Encode, levels, levels)?;
+        scratch_image_from_mips(new_format, metadata.width, metadata.height, &mut mips)
+    }
+
+    /// Generates `levels` mip levels of `image`, or the full chain when `levels` is 0.
+    /// DirectXTex generates the whole chain in one call, so the progress hooks see its start and end.
+    fn generate_mips(
+        &self,
+        image: &ScratchImage,
+        filter: TEX_FILTER_FLAGS,
+        levels: usize,
+    ) -> Result<ScratchImage, TexturePackerError> {
+        let metadata = image.metadata();
+        let full_chain =
+            (usize::BITS - metadata.width.max(metadata.height).leading_zeros()) as usize;
+        let steps = match levels {
+            0 => full_chain,
+            levels if levels > full_chain => {
+                return Err(PackingError(format!(
+                    "A {}x{} image has at most {full_chain} mip levels, {levels} were requested",
+                    metadata.width, metadata.height
+                )))
+            }
+            levels => levels,
+        }
+        .saturating_sub(1);
+
+        // DirectXTex rejects a chain of a single level
+        if steps == 0 {
+            let mut mips = vec![image
+                .image(0, 0, 0)
+                .and_then(Self::process_mip_image)
+                .ok_or(PackingError("The source image is empty".to_string()))?];
+            return scratch_image_from_mips(
+                metadata.format,
+                metadata.width,
+                metadata.height,
+                &mut mips,
+            );
+        }
+
+        self.hooks.report(Stage::GenerateMips, 0, steps)?;
+        let generated = image.generate_mip_maps(filter, levels)?;
+        self.hooks.report(Stage::GenerateMips, steps, steps)?;
+        Ok(generated)
+    }
+
+    /// Encodes the source image to the target format without generating any mip levels.
+    pub(crate) fn encode_source_level(self) -> Result<Vec<u8>, TexturePackerError> {
+        let target_format = self.params.format.into();
+        let image = if self.image.metadata().format != target_format {
+            self.encode_to_format(&self.image, target_format)?
+        } else {
+            self.image
+        };
+
+        image
+            .image(0, 0, 0)
+            .and_then(Self::process_mip_image)
+            .ok_or(PackingError(
+                "Failed to encode the source image".to_string(),
+            ))
+    }
+
     /// Final build method to create a TextureMap.
-    pub fn build(self, woa_version: WoaVersion) -> Result<TextureMap, TexturePackerError> {
+    pub fn build(mut self, woa_version: WoaVersion) -> Result<TextureMap, TexturePackerError> {
+        if let Some(strength) = self.normal_from_height.take() {
+            self = self.derive_normal_map(strength)?;
+        }
+
+        if self.auto_format {
+            self.params.format = self.analyze_format()?.format;
+        }
+
+        let chain = self.encode_mip_chain()?;
+
+        #[cfg(not(feature = "unstable"))]
+        let params = match woa_version {
+            WoaVersion::HM3 => self.params,
+            //detached from builder
+            WoaVersion::HM2016 | WoaVersion::HM2 => TextureMapParameters {
+                flags: TextureFlagsInner::default(),
+                ..self.params
+            },
+        };
+        #[cfg(feature = "unstable")]
+        let params = self.params;
+
+        let texture_map = assemble_texture_map(
+            woa_version,
+            &params,
+            self.atlas_data,
+            self.use_mipblock1 || self.text_only,
+            chain,
+            &self.hooks,
+        )?;
+        if self.text_only {
+            texture_map.text_only()
+        } else {
+            Ok(texture_map)
+        }
+    }
+
+    /// Generates the mip chain of the source image and encodes every level to the target format.
+    pub(crate) fn encode_mip_chain(&self) -> Result<MipChain, TexturePackerError> {
         let width = self.image.metadata().width as u16;
         let height = self.image.metadata().height as u16;
 
@@ -373,18 +1085,30 @@ impl TextureMapBuilder {
             )));
         }
 
-        let mut filter = match self.params.mip_filter {
-            MipFilter::Nearest => TEX_FILTER_FLAGS::TEX_FILTER_POINT,
-            MipFilter::Linear => TEX_FILTER_FLAGS::TEX_FILTER_LINEAR,
-            MipFilter::Cubic => TEX_FILTER_FLAGS::TEX_FILTER_CUBIC,
-            MipFilter::Box => TEX_FILTER_FLAGS::TEX_FILTER_BOX,
-        };
+        if self.keep_authored_mips && self.image.metadata().mip_levels > 1 {
+            return Ok(MipChain {
+                width,
+                height,
+                mips: self.encode_authored_mip_chain()?,
+            });
+        }
+
+        if self.params.mip_generation.is_enabled() {
+            return Ok(MipChain {
+                width,
+                height,
+                mips: self.encode_filtered_mip_chain()?,
+            });
+        }
+
+        let mut filter = TEX_FILTER_FLAGS::from(self.params.mip_filter);
 
         // if cfg!(windows) {
-            filter |= TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC;
+        filter |= TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC;
         // }
 
-        let mut image = self.image.generate_mip_maps(
+        let mut image = self.generate_mips(
+            &self.image,
             filter,
             match self.params.num_mip_levels {
                 MipLevels::All => 0,
@@ -394,146 +1118,198 @@ impl TextureMapBuilder {
 
         let target_format = self.params.format.into();
         if self.image.metadata().format != target_format {
-            image = Self::convert_to_format(image, target_format)?;
-        }
-
-        let generated_mip_levels = image.metadata().mip_levels.clamp(0, 14) as u8;
-        let num_mip_levels = generated_mip_levels;
-
-        // Handle mip sizes
-        let mut mip_sizes = [0u32; 14];
-        for i in 0..generated_mip_levels as usize {
-            let last: u32 = i
-                .checked_sub(1)
-                .and_then(|index| mip_sizes.get(index))
-                .copied()
-                .unwrap_or(0);
-            mip_sizes[i] =
-                last + image.image(i, 0, 0).map(|img| img.slice_pitch).unwrap_or(0) as u32;
-        }
-
-        let mut data = Self::serialize_mipmaps(&image, generated_mip_levels)?;
-        let mut compressed_mip_sizes = mip_sizes;
-        if woa_version == WoaVersion::HM3 {
-            let mut compressed_image_buffer = vec![];
-            let mut cursor = Cursor::new(&data);
-            for mip in 0..generated_mip_levels as usize {
-                if let Some(mip_image) = image.image(mip, 0, 0) {
-                    let mut mip_data = vec![0u8; mip_image.slice_pitch];
-                    cursor
-                        .read(mip_data.as_mut_slice())
-                        .map_err(TexturePackerError::IoError)?;
-                    let mip_compressed = lz4::block::compress(
-                        &mip_data,
-                        Some(CompressionMode::HIGHCOMPRESSION(12)),
-                        false,
-                    )
-                    .map_err(|_| PackingError(format!("Failed to compress mip level {mip}")))?;
-
-                    let last: u32 = mip
-                        .checked_sub(1)
-                        .and_then(|index| compressed_mip_sizes.get(index))
-                        .copied()
-                        .unwrap_or(0);
-                    compressed_mip_sizes[mip] = last
-                        + image
-                            .image(mip, 0, 0)
-                            .map(|_| mip_compressed.len())
-                            .unwrap_or(0) as u32;
-
-                    compressed_image_buffer.extend(mip_compressed);
-                }
-            }
-            data = compressed_image_buffer;
+            image = self.encode_to_format(&image, target_format)?;
         }
 
-        let texture_data = if self.use_mipblock1 {
-            TextureData::Mipblock1(MipblockData {
-                video_memory_requirement: (mip_sizes.first().copied().unwrap_or(0x0)
-                    + mip_sizes.get(1).copied().unwrap_or(0x0))
-                    as usize,
-                header: vec![],
-                data,
-            })
+        let generated_mip_levels = image.metadata().mip_levels.clamp(0, MAX_MIP_LEVELS) as u8;
+        Ok(MipChain {
+            width,
+            height,
+            mips: Self::serialize_mipmaps(&image, generated_mip_levels)?,
+        })
+    }
+
+    /// The amount of mip levels the build will produce for the source image.
+    fn target_mip_levels(&self) -> usize {
+        let metadata = self.image.metadata();
+        let max_levels =
+            (usize::BITS - metadata.width.max(metadata.height).leading_zeros()) as usize;
+        match self.params.num_mip_levels {
+            MipLevels::All => max_levels,
+            MipLevels::Limit(n) => (n as usize).clamp(1, max_levels),
+        }
+        .min(MAX_MIP_LEVELS)
+    }
+
+    /// Generates the mip chain in float precision according to the [`MipGenerationOptions`].
+    fn encode_filtered_mip_chain(&self) -> Result<Vec<Vec<u8>>, TexturePackerError> {
+        let metadata = self.image.metadata();
+        let float_format = DXGI_FORMAT::DXGI_FORMAT_R32G32B32A32_FLOAT;
+        let converted = if metadata.format == float_format {
+            None
+        } else if metadata.format.is_compressed() {
+            Some(
+                directxtex::decompress(self.image.images(), metadata, float_format)
+                    .map_err(DirectXTexError)?,
+            )
         } else {
-            TextureData::Tex(data)
+            Some(
+                directxtex::convert(
+                    self.image.images(),
+                    metadata,
+                    float_format,
+                    TEX_FILTER_FLAGS::TEX_FILTER_DEFAULT
+                        | TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC,
+                    TEX_THRESHOLD_DEFAULT,
+                )
+                .map_err(DirectXTexError)?,
+            )
         };
 
-        let texture_map_inner = match woa_version {
-            WoaVersion::HM2016 => {
-                let header = TextureMapHeaderV1 {
-                    type_: self.params.texture_type,
-                    texd_identifier: self.params.texd_identifier,
-                    #[cfg(feature = "unstable")]
-                    flags: self.params.flags,
-                    #[cfg(not(feature = "unstable"))]
-                    flags: TextureFlagsInner::default(), //detached from builder
-                    width,
-                    height,
-                    format: self.params.format,
-                    num_mip_levels,
-                    default_mip_level: self.params.default_mip_level,
-                    interpret_as: self.params.interpret_as,
-                    dimensions: self.params.dimensions,
-                    mip_sizes,
-                    has_atlas: self.atlas_data.is_some(),
-                };
-                TextureMapInner {
-                    header,
-                    atlas_data: self.atlas_data,
-                    data: texture_data,
-                }
-                .into()
-            }
-            WoaVersion::HM2 => {
-                let header = TextureMapHeaderV2 {
-                    type_: self.params.texture_type,
-                    texd_identifier: self.params.texd_identifier,
-                    #[cfg(feature = "unstable")]
-                    flags: self.params.flags,
-                    #[cfg(not(feature = "unstable"))]
-                    flags: TextureFlagsInner::default(), //detached from builder
-                    width,
-                    height,
-                    format: self.params.format,
-                    num_mip_levels,
-                    default_mip_level: max(self.params.default_mip_level, 1), //H2 crashes with index 0
-                    mip_sizes,
-                    compressed_mip_sizes,
-                    has_atlas: self.atlas_data.is_some(),
-                };
-                TextureMapInner {
-                    header,
-                    atlas_data: self.atlas_data,
-                    data: texture_data,
-                }
-                .into()
-            }
-            WoaVersion::HM3 => {
-                let header = TextureMapHeaderV3 {
-                    type_: self.params.texture_type,
-                    flags: self.params.flags,
-                    width,
-                    height,
-                    format: self.params.format,
-                    num_mip_levels,
-                    default_mip_level: self.params.default_mip_level,
-                    interpret_as: self.params.interpret_as,
-                    dimensions: self.params.dimensions,
-                    mip_sizes,
-                    compressed_mip_sizes,
-                    has_atlas: self.atlas_data.is_some(),
-                };
-                TextureMapInner {
-                    header,
-                    atlas_data: self.atlas_data,
-                    data: texture_data,
-                }
-                .into()
+        let bytes = converted
+            .as_ref()
+            .unwrap_or(&self.image)
+            .image(0, 0, 0)
+            .and_then(Self::process_mip_image)
+            .ok_or(PackingError("The source image is empty".to_string()))?;
+        let base = MipLevelF32 {
+            width: metadata.width,
+            height: metadata.height,
+            pixels: bytes
+                .chunks_exact(16)
+                .map(|pixel| {
+                    [0, 4, 8, 12].map(|i| {
+                        f32::from_le_bytes([pixel[i], pixel[i + 1], pixel[i + 2], pixel[i + 3]])
+                    })
+                })
+                .collect(),
+        };
+
+        let levels = self.target_mip_levels();
+        let steps = levels.saturating_sub(1);
+        let two_channel = matches!(
+            metadata.format,
+            DXGI_FORMAT::DXGI_FORMAT_R8G8_UNORM
+                | DXGI_FORMAT::DXGI_FORMAT_R8G8_TYPELESS
+                | DXGI_FORMAT::DXGI_FORMAT_R16G16_UNORM
+                | DXGI_FORMAT::DXGI_FORMAT_R16G16_FLOAT
+                | DXGI_FORMAT::DXGI_FORMAT_R32G32_FLOAT
+                | DXGI_FORMAT::DXGI_FORMAT_BC5_UNORM
+                | DXGI_FORMAT::DXGI_FORMAT_BC5_TYPELESS
+        );
+        let chain = mipgen::generate_mip_chain(
+            base,
+            levels,
+            &self.params.mip_generation,
+            self.params.mip_filter,
+            two_channel,
+            !self.params.format.is_float(),
+            |level| self.hooks.report(Stage::GenerateMips, level - 1, steps),
+        )?;
+        let mut mips = chain
+            .into_iter()
+            .map(|level| {
+                level
+                    .pixels
+                    .iter()
+                    .flat_map(|pixel| pixel.iter().flat_map(|c| c.to_le_bytes()))
+                    .collect::<Vec<u8>>()
+            })
+            .collect::<Vec<_>>();
+        self.hooks.report(Stage::GenerateMips, steps, steps)?;
+
+        let mut image =
+            scratch_image_from_mips(float_format, metadata.width, metadata.height, &mut mips)?;
+        let target_format = self.params.format.into();
+        if target_format != float_format {
+            image = self.encode_to_format(&image, target_format)?;
+        }
+        Self::serialize_mipmaps(&image, levels as u8)
+    }
+
+    /// Encodes the mip levels of the source image as-is and generates the levels that are missing.
+    fn encode_authored_mip_chain(&self) -> Result<Vec<Vec<u8>>, TexturePackerError> {
+        let metadata = self.image.metadata();
+        let (width, height) = (metadata.width, metadata.height);
+        let total_levels = self.target_mip_levels();
+        let authored_levels = metadata.mip_levels.min(total_levels);
+
+        for level in 0..authored_levels {
+            let image = self
+                .image
+                .image(level, 0, 0)
+                .ok_or(PackingError(format!("Missing mip level {level}")))?;
+            let expected = ((width >> level).max(1), (height >> level).max(1));
+            if (image.width, image.height) != expected {
+                return Err(PackingError(format!(
+                    "Authored mip level {level} is {}x{}, expected {}x{}",
+                    image.width, image.height, expected.0, expected.1
+                )));
             }
+        }
+
+        let target_format: DXGI_FORMAT = self.params.format.into();
+        let needs_conversion = metadata.format != target_format;
+        let needs_generation = authored_levels < total_levels;
+
+        let decompressed =
+            if metadata.format.is_compressed() && (needs_conversion || needs_generation) {
+                Some(
+                    directxtex::decompress(
+                        self.image.images(),
+                        self.image.metadata(),
+                        DXGI_FORMAT::DXGI_FORMAT_R8G8B8A8_UNORM,
+                    )
+                    .map_err(DirectXTexError)?,
+                )
+            } else {
+                None
+            };
+        let source = decompressed.as_ref().unwrap_or(&self.image);
+
+        let mut mips = if needs_conversion {
+            let converted = self.encode_to_format(source, target_format)?;
+            Self::serialize_mipmaps(&converted, authored_levels as u8)?
+        } else {
+            Self::serialize_mipmaps(&self.image, authored_levels as u8)?
         };
 
-        Ok(texture_map_inner)
+        if needs_generation {
+            let filter = TEX_FILTER_FLAGS::from(self.params.mip_filter)
+                | TEX_FILTER_FLAGS::TEX_FILTER_FORCE_NON_WIC;
+            let smallest = source
+                .image(authored_levels - 1, 0, 0)
+                .ok_or(PackingError(format!(
+                    "Missing mip level {}",
+                    authored_levels - 1
+                )))?;
+            let generated_levels = total_levels - authored_levels + 1;
+            // copied instead of resized to its own size, the box filter only supports halving
+            let smallest = scratch_image_from_mips(
+                smallest.format,
+                smallest.width,
+                smallest.height,
+                &mut [
+                    Self::process_mip_image(smallest).ok_or(PackingError(format!(
+                        "Mip level {} is empty",
+                        authored_levels - 1
+                    )))?,
+                ],
+            )?;
+            let mut generated = self.generate_mips(&smallest, filter, generated_levels)?;
+            if generated.metadata().format != target_format {
+                generated = self.encode_to_format(&generated, target_format)?;
+            }
+
+            // the first generated level is the smallest authored level
+            mips.extend(
+                Self::serialize_mipmaps(&generated, generated_levels as u8)?
+                    .into_iter()
+                    .skip(1),
+            );
+        }
+
+        Ok(mips)
     }
 
     fn process_mip_image(mip_image: &Image) -> Option<Vec<u8>> {
@@ -548,12 +1324,11 @@ impl TextureMapBuilder {
     fn serialize_mipmaps(
         image: &directxtex::ScratchImage,
         mip_levels: u8,
-    ) -> Result<Vec<u8>, TexturePackerError> {
+    ) -> Result<Vec<Vec<u8>>, TexturePackerError> {
         let mut serialized = Vec::new();
         for mip in 0..mip_levels {
             if let Some(mip_image) = image.image(mip as usize, 0, 0) {
-                let buffer = Self::process_mip_image(mip_image).unwrap_or(vec![]);
-                serialized.extend_from_slice(buffer.as_slice());
+                serialized.push(Self::process_mip_image(mip_image).unwrap_or_default());
             } else {
                 return Err(PackingError(format!("Missing mip level {mip}")));
             }
@@ -561,3 +1336,236 @@ impl TextureMapBuilder {
         Ok(serialized)
     }
 }
+
+/// Wraps tightly packed mip levels in a [`ScratchImage`] by passing them through an in-memory DDS.
+pub(crate) fn scratch_image_from_mips(
+    format: DXGI_FORMAT,
+    width: usize,
+    height: usize,
+    mips: &mut [Vec<u8>],
+) -> Result<ScratchImage, TexturePackerError> {
+    let images = mips
+        .iter_mut()
+        .enumerate()
+        .map(|(level, data)| {
+            let mip_width = (width >> level).max(1);
+            let mip_height = (height >> level).max(1);
+            let pitch = format
+                .compute_pitch(mip_width, mip_height, CP_FLAGS::CP_FLAGS_NONE)
+                .map_err(DirectXTexError)?;
+            if data.len() < pitch.slice {
+                return Err(PackingError(format!(
+                    "Mip level {level} holds {} bytes, expected {}",
+                    data.len(),
+                    pitch.slice
+                )));
+            }
+
+            Ok(Image {
+                width: mip_width,
+                height: mip_height,
+                format,
+                row_pitch: pitch.row,
+                slice_pitch: pitch.slice,
+                pixels: data.as_mut_ptr(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let meta_data = TexMetadata {
+        width,
+        height,
+        depth: 1,
+        array_size: 1,
+        mip_levels: images.len(),
+        misc_flags: 0,
+        misc_flags2: 0,
+        format,
+        dimension: TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D,
+    };
+
+    let blob = directxtex::save_dds(&images, &meta_data, DDS_FLAGS::DDS_FLAGS_FORCE_DX10_EXT)
+        .map_err(DirectXTexError)?;
+    ScratchImage::load_dds(
+        blob.buffer(),
+        DDS_FLAGS::DDS_FLAGS_FORCE_DX10_EXT,
+        None,
+        None,
+    )
+    .map_err(DirectXTexError)
+}
+
+/// LZ4 compresses every mip level on its own, in parallel with the `rayon` feature.
+/// Every finished level is reported to `hooks`, the output doesn't depend on the order they finish in.
+fn compress_mips(
+    mips: &[Vec<u8>],
+    hooks: &ProgressHooks,
+) -> Result<Vec<Vec<u8>>, TexturePackerError> {
+    hooks.report(Stage::Compress, 0, mips.len())?;
+    let completed = AtomicUsize::new(0);
+    let compress = |(mip, mip_data): (usize, &Vec<u8>)| -> Result<Vec<u8>, TexturePackerError> {
+        let mip_compressed =
+            lz4::block::compress(mip_data, Some(CompressionMode::HIGHCOMPRESSION(12)), false)
+                .map_err(|_| PackingError(format!("Failed to compress mip level {mip}")))?;
+        let completed = completed.fetch_add(1, Ordering::Relaxed) + 1;
+        hooks.report(Stage::Compress, completed, mips.len())?;
+        Ok(mip_compressed)
+    };
+
+    #[cfg(feature = "rayon")]
+    let compressed = {
+        use rayon::prelude::*;
+        mips.par_iter().enumerate().map(compress).collect()
+    };
+    #[cfg(not(feature = "rayon"))]
+    let compressed = mips.iter().enumerate().map(compress).collect();
+    compressed
+}
+
+/// A full chain of encoded mip levels, ordered from the largest to the smallest level.
+pub(crate) struct MipChain {
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+    pub(crate) mips: Vec<Vec<u8>>,
+}
+
+/// Lays out already encoded mip levels as a [`TextureMap`] for the given version.
+/// The flags in `params` are written as-is, callers are responsible for detaching them if needed.
+/// The LZ4 compression of every mip level is reported to `hooks`.
+pub(crate) fn assemble_texture_map(
+    woa_version: WoaVersion,
+    params: &TextureMapParameters,
+    atlas_data: Option<AtlasData>,
+    use_mipblock1: bool,
+    chain: MipChain,
+    hooks: &ProgressHooks,
+) -> Result<TextureMap, TexturePackerError> {
+    if chain.mips.is_empty() {
+        return Err(PackingError("There are no mip levels to pack".to_string()));
+    }
+
+    let num_mip_levels = chain.mips.len().min(MAX_MIP_LEVELS) as u8;
+    let tiling = Tiling::from_flags(params.flags);
+    let mips = chain.mips[..num_mip_levels as usize]
+        .iter()
+        .enumerate()
+        .map(|(level, mip)| {
+            let width = (chain.width as usize >> level).max(1);
+            let height = (chain.height as usize >> level).max(1);
+            tiling::tile(tiling, params.format, width, height, mip)
+                .map_err(|e| PackingError(format!("Failed to tile mip level {level}: {e}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Handle mip sizes
+    let mut mip_sizes = [0u32; MAX_MIP_LEVELS];
+    let mut offset = 0u32;
+    for (i, mip) in mips.iter().enumerate() {
+        offset += mip.len() as u32;
+        mip_sizes[i] = offset;
+    }
+
+    let mut compressed_mip_sizes = mip_sizes;
+    let data = if woa_version == WoaVersion::HM3 {
+        let compressed_mips = compress_mips(&mips, hooks)?;
+        let mut offset = 0u32;
+        for (mip, mip_compressed) in compressed_mips.iter().enumerate() {
+            offset += mip_compressed.len() as u32;
+            compressed_mip_sizes[mip] = offset;
+        }
+        compressed_mips.concat()
+    } else {
+        mips.concat()
+    };
+
+    let texture_data = if use_mipblock1 {
+        TextureData::Mipblock1(MipblockData {
+            video_memory_requirement: (mip_sizes.first().copied().unwrap_or(0x0)
+                + mip_sizes.get(1).copied().unwrap_or(0x0))
+                as usize,
+            header: vec![],
+            data,
+        })
+    } else {
+        TextureData::Tex(data)
+    };
+
+    let width = chain.width;
+    let height = chain.height;
+    let texture_map: TextureMap = match woa_version {
+        WoaVersion::HM2016 => {
+            let header = TextureMapHeaderV1 {
+                type_: params.texture_type,
+                texd_identifier: params.texd_identifier,
+                flags: params.flags,
+                width,
+                height,
+                format: params.format,
+                num_mip_levels,
+                default_mip_level: params.default_mip_level,
+                interpret_as: params.interpret_as,
+                dimensions: params.dimensions,
+                mip_sizes,
+                has_atlas: atlas_data.is_some(),
+                preserved: None,
+            };
+            TextureMapInner {
+                header,
+                atlas_data,
+                data: texture_data,
+                original_text_data: None,
+            }
+            .into()
+        }
+        WoaVersion::HM2 => {
+            let header = TextureMapHeaderV2 {
+                type_: params.texture_type,
+                texd_identifier: params.texd_identifier,
+                flags: params.flags,
+                width,
+                height,
+                format: params.format,
+                num_mip_levels,
+                default_mip_level: max(params.default_mip_level, 1), //H2 crashes with index 0
+                mip_sizes,
+                compressed_mip_sizes,
+                has_atlas: atlas_data.is_some(),
+                preserved: None,
+            };
+            TextureMapInner {
+                header,
+                atlas_data,
+                data: texture_data,
+                original_text_data: None,
+            }
+            .into()
+        }
+        WoaVersion::HM3 => {
+            let header = TextureMapHeaderV3 {
+                type_: params.texture_type,
+                flags: params.flags,
+                width,
+                height,
+                format: params.format,
+                num_mip_levels,
+                default_mip_level: params.default_mip_level,
+                interpret_as: params.interpret_as,
+                dimensions: params.dimensions,
+                mip_sizes,
+                compressed_mip_sizes,
+                has_atlas: atlas_data.is_some(),
+                preserved: None,
+            };
+            TextureMapInner {
+                header,
+                atlas_data,
+                data: texture_data,
+                original_text_data: None,
+            }
+            .into()
+        }
+    };
+    texture_map.validate()?;
+
+    Ok(texture_map)
+}
@@ -0,0 +1,109 @@
+//! Progress reporting and cancellation for texture builds and batch tools.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The stage a long running operation is in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stage {
+    /// Generating the mip chain of the source image.
+    GenerateMips,
+    /// Encoding the mip levels to the target format, this includes BC compression.
+    Encode,
+    /// LZ4 compressing the encoded mip levels.
+    Compress,
+    /// Reading the textures of a directory or partition.
+    Scan,
+}
+
+/// A progress update, `completed` out of `total` steps of `stage` are done.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub stage: Stage,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// A flag that can be shared with another thread to stop a running operation.
+/// The operation stops at the next step, e.g. the next mip level or file.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Returned when an operation was stopped through its [`CancellationToken`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("The operation was cancelled")]
+pub struct Cancelled;
+
+/// The optional progress callback and cancellation token of an operation.
+#[derive(Clone, Default)]
+pub struct ProgressHooks {
+    callback: Option<Arc<dyn Fn(Progress) + Send + Sync>>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl fmt::Debug for ProgressHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressHooks")
+            .field("callback", &self.callback.is_some())
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
+
+impl ProgressHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `callback` every time a step of the operation starts or the last step finishes.
+    pub fn with_callback(mut self, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Reports the progress and fails if the operation was cancelled in the meantime.
+    pub(crate) fn report(
+        &self,
+        stage: Stage,
+        completed: usize,
+        total: usize,
+    ) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            return Err(Cancelled);
+        }
+        if let Some(callback) = &self.callback {
+            callback(Progress {
+                stage,
+                completed,
+                total,
+            });
+        }
+        Ok(())
+    }
+}
@@ -3,6 +3,8 @@ use binrw::{BinRead, BinWrite};
 use rpkg_rs::{GlacierResource, GlacierResourceError};
 use crate::mipblock::MipblockData;
 use crate::pack::TexturePackerError;
+use crate::progress::{Cancelled, ProgressHooks};
+use crate::scan::{scan_items, DuplicateIndex, TextureStatistics};
 use crate::texture_map::TextureMap;
 use crate::WoaVersion;
 
@@ -32,13 +34,15 @@ impl GlacierResource for TextureMap {
 
     fn process_data<R: AsRef<[u8]>>(woa_version: rpkg_rs::WoaVersion, data: R) -> Result<Self::Output, GlacierResourceError> {
         let mut stream = Cursor::new(data);
-        TextureMap::read_le_args(&mut stream, (WoaVersion::from(woa_version), )).map_err(|e| GlacierResourceError::ReadError(e.to_string()))
+        let texture_map = TextureMap::read_le_args(&mut stream, (WoaVersion::from(woa_version), )).map_err(|e| GlacierResourceError::ReadError(e.to_string()))?;
+        texture_map.validate().map_err(|e| GlacierResourceError::ReadError(e.to_string()))?;
+        Ok(texture_map)
     }
 
     fn serialize(&self, _: rpkg_rs::WoaVersion) -> Result<Vec<u8>, GlacierResourceError> {
         //TODO: woa version gets ignored currently. Getting the packer to accept TextureMap would allow for easy porting.
         let mut writer = Cursor::new(Vec::new());
-        self.write_le_args(&mut writer, ())
+        self.write_le_args(&mut writer, (false,))
             .map_err(TexturePackerError::SerializationError).map_err(|e| GlacierResourceError::ReadError(e.to_string()))?; //TODO: change this
         Ok(writer.into_inner())
     }
@@ -110,6 +114,7 @@ pub fn full_texture(manager: &rpkg_rs::resource::partition_manager::PartitionMan
 
     let mut stream = Cursor::new(data);
     let mut texture_map = TextureMap::read_le_args(&mut stream, (WoaVersion::from(woa_version), )).map_err(|e| GlacierResourceError::ReadError(e.to_string()))?;
+    texture_map.validate().map_err(|e| GlacierResourceError::ReadError(e.to_string()))?;
 
     if let Some((rrid, _)) = res_info.references().first(){
         let texd_data = manager.read_resource_from("chunk0".parse().unwrap(), *rrid).map_err(|e| GlacierResourceError::ReadError(format!("Tried to load broken depend: {e}")))?;
@@ -117,4 +122,68 @@ pub fn full_texture(manager: &rpkg_rs::resource::partition_manager::PartitionMan
         texture_map.set_mipblock1(mipblock);
     }
     Ok(texture_map)
-}
\ No newline at end of file
+}
+/// Adds every TEXT resource in a partition to the histogram, the RRID is used as the example identifier.
+pub fn scan_partition(manager: &rpkg_rs::resource::partition_manager::PartitionManager, partition: &rpkg_rs::resource::resource_partition::ResourcePartition, woa_version: rpkg_rs::WoaVersion, statistics: &mut TextureStatistics) {
+    let _ = scan_partition_with_hooks(manager, partition, woa_version, statistics, &ProgressHooks::default());
+}
+
+/// [`scan_partition`], reporting every TEXT resource to `hooks`.
+/// The resources are read in parallel with the `rayon` feature, `hooks` may then be called from several threads.
+/// The resources scanned before the cancellation stay in the histogram.
+pub fn scan_partition_with_hooks(manager: &rpkg_rs::resource::partition_manager::PartitionManager, partition: &rpkg_rs::resource::resource_partition::ResourcePartition, woa_version: rpkg_rs::WoaVersion, statistics: &mut TextureStatistics, hooks: &ProgressHooks) -> Result<(), Cancelled> {
+    let resources = partition.latest_resources().into_iter()
+        .filter(|(resource, _)| resource.data_type() == "TEXT")
+        .collect::<Vec<_>>();
+
+    let (found, result) = scan_items(&resources, hooks, || statistics.empty(), |statistics, (resource, _)| {
+        let id = resource.rrid().to_string();
+        let texture = manager.read_resource_from(partition.partition_info().id.clone(), *resource.rrid())
+            .map_err(|e| GlacierResourceError::ReadError(e.to_string()))
+            .and_then(|data| TextureMap::process_data(woa_version, data));
+
+        match texture {
+            Ok(texture) => statistics.add(id, &texture),
+            Err(e) => statistics.add_failure(id, e),
+        }
+    }, TextureStatistics::merge);
+    *statistics = std::mem::take(statistics).merge(found);
+    result
+}
+
+/// Adds every TEXT resource in a partition to the duplicate index, together with its TEXD if the partition holds it.
+/// Textures that need a TEXD the partition doesn't hold are recorded as failures.
+pub fn find_duplicates_in_partition(manager: &rpkg_rs::resource::partition_manager::PartitionManager, partition: &rpkg_rs::resource::resource_partition::ResourcePartition, woa_version: rpkg_rs::WoaVersion, index: &mut DuplicateIndex) {
+    let _ = find_duplicates_in_partition_with_hooks(manager, partition, woa_version, index, &ProgressHooks::default());
+}
+
+/// [`find_duplicates_in_partition`], reporting every TEXT resource to `hooks`.
+/// The resources are hashed in parallel with the `rayon` feature, `hooks` may then be called from several threads.
+/// The resources hashed before the cancellation stay in the index.
+pub fn find_duplicates_in_partition_with_hooks(manager: &rpkg_rs::resource::partition_manager::PartitionManager, partition: &rpkg_rs::resource::resource_partition::ResourcePartition, woa_version: rpkg_rs::WoaVersion, index: &mut DuplicateIndex, hooks: &ProgressHooks) -> Result<(), Cancelled> {
+    let resources = partition.latest_resources().into_iter()
+        .filter(|(resource, _)| resource.data_type() == "TEXT")
+        .collect::<Vec<_>>();
+
+    let (found, result) = scan_items(&resources, hooks, DuplicateIndex::new, |index, (resource, _)| {
+        let id = resource.rrid().to_string();
+        let texture = manager.read_resource_from(partition.partition_info().id.clone(), *resource.rrid())
+            .map_err(|e| GlacierResourceError::ReadError(e.to_string()))
+            .and_then(|data| TextureMap::process_data(woa_version, data))
+            .and_then(|mut texture| {
+                if let Some((texd_rrid, _)) = resource.references().first() {
+                    if let Ok(texd_data) = manager.read_resource_from(partition.partition_info().id.clone(), *texd_rrid) {
+                        texture.set_mipblock1(MipblockData::process_data(woa_version, texd_data)?);
+                    }
+                }
+                Ok(texture)
+            });
+
+        match texture {
+            Ok(texture) => index.add(id, &texture),
+            Err(e) => index.add_failure(id, e),
+        }
+    }, DuplicateIndex::merge);
+    *index = std::mem::take(index).merge(found);
+    result
+}
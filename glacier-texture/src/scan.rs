@@ -0,0 +1,466 @@
+use crate::enums::{Dimensions, InterpretAs, RenderFormat, TextureType};
+use crate::mipblock::MipblockData;
+use crate::progress::{Cancelled, ProgressHooks, Stage};
+use crate::texture_map::TextureMap;
+use crate::WoaVersion;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::{fs, io};
+
+/// The combination of header values textures are grouped by.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextureSignature {
+    pub version: WoaVersion,
+    pub texture_type: TextureType,
+    pub interpret_as: Option<InterpretAs>,
+    pub format: RenderFormat,
+    /// Whether the texture is a 2D, cube or volume texture. HM2 only stores 2D textures.
+    pub dimensions: Dimensions,
+    /// The raw flags bitfield, including the bits that are not exposed by [`crate::enums::TextureFlags`].
+    pub flags: u32,
+    /// The width of the largest mip level, as stored in the header.
+    pub width: usize,
+    /// The height of the largest mip level, as stored in the header.
+    pub height: usize,
+    /// The number of mip levels, as stored in the header.
+    pub num_mip_levels: usize,
+    pub text_scale: usize,
+    pub has_atlas: bool,
+}
+
+impl TextureSignature {
+    pub fn from_texture_map(texture: &TextureMap) -> Self {
+        let (width, height) = texture.texd_size();
+        Self {
+            version: texture.version(),
+            texture_type: texture.texture_type(),
+            interpret_as: texture.interpret_as(),
+            format: texture.format(),
+            dimensions: texture.dimensions(),
+            flags: texture.flags().bits(),
+            width,
+            height,
+            num_mip_levels: texture.texd_mip_levels(),
+            text_scale: texture.text_scale(),
+            has_atlas: texture.has_atlas(),
+        }
+    }
+}
+
+/// A single row of the histogram.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignatureCount {
+    #[serde(flatten)]
+    pub signature: TextureSignature,
+    pub count: usize,
+    /// The lexicographically smallest identifiers (usually RRIDs) of the textures with this signature.
+    pub examples: Vec<String>,
+}
+
+/// Histogram of texture header combinations, collected over a corpus of TEXT resources.
+#[derive(Debug, Clone)]
+pub struct TextureStatistics {
+    counts: HashMap<TextureSignature, (usize, Vec<String>)>,
+    failures: Vec<(String, String)>,
+    max_examples: usize,
+}
+
+impl Default for TextureStatistics {
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+impl TextureStatistics {
+    /// Creates an empty histogram that keeps up to `max_examples` identifiers per signature.
+    pub fn new(max_examples: usize) -> Self {
+        Self {
+            counts: HashMap::new(),
+            failures: vec![],
+            max_examples,
+        }
+    }
+
+    /// An empty histogram that keeps as many examples as this one.
+    pub(crate) fn empty(&self) -> Self {
+        Self::new(self.max_examples)
+    }
+
+    pub fn add(&mut self, id: impl Into<String>, texture: &TextureMap) {
+        let (count, examples) = self
+            .counts
+            .entry(TextureSignature::from_texture_map(texture))
+            .or_default();
+        *count += 1;
+        let id = id.into();
+        let position = examples.partition_point(|example| *example <= id);
+        if position < self.max_examples {
+            examples.insert(position, id);
+            examples.truncate(self.max_examples);
+        }
+    }
+
+    /// Records a resource that could not be parsed.
+    pub fn add_failure(&mut self, id: impl Into<String>, error: impl ToString) {
+        self.failures.push((id.into(), error.to_string()));
+    }
+
+    /// Combines two histograms, this makes it possible to scan in parallel.
+    pub fn merge(mut self, other: TextureStatistics) -> Self {
+        for (signature, (count, examples)) in other.counts {
+            let (total, kept) = self.counts.entry(signature).or_default();
+            *total += count;
+            kept.extend(examples);
+            kept.sort();
+            kept.truncate(self.max_examples);
+        }
+        self.failures.extend(other.failures);
+        self.failures.sort();
+        self
+    }
+
+    /// The total amount of textures that were added.
+    pub fn total(&self) -> usize {
+        self.counts.values().map(|(count, _)| count).sum()
+    }
+
+    /// The resources that failed to parse, together with the error message.
+    pub fn failures(&self) -> &[(String, String)] {
+        &self.failures
+    }
+
+    /// Every signature with its count, sorted from most to least common and then by signature.
+    pub fn entries(&self) -> Vec<SignatureCount> {
+        let mut entries = self
+            .counts
+            .iter()
+            .map(|(signature, (count, examples))| SignatureCount {
+                signature: signature.clone(),
+                count: *count,
+                examples: examples.clone(),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.signature.cmp(&b.signature))
+        });
+        entries
+    }
+
+    /// Formats the histogram as CSV, examples are separated by a `;`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("version,type,interpret_as,format,dimensions,flags,width,height,num_mip_levels,text_scale,has_atlas,count,examples\n");
+        for entry in self.entries() {
+            let signature = &entry.signature;
+            csv.push_str(&format!(
+                "{:?},{:?},{},{:?},{:?},{:#x},{},{},{},{},{},{},{}\n",
+                signature.version,
+                signature.texture_type,
+                signature
+                    .interpret_as
+                    .map(|interpret_as| format!("{interpret_as:?}"))
+                    .unwrap_or_default(),
+                signature.format,
+                signature.dimensions,
+                signature.flags,
+                signature.width,
+                signature.height,
+                signature.num_mip_levels,
+                signature.text_scale,
+                signature.has_atlas,
+                entry.count,
+                entry.examples.join(";"),
+            ));
+        }
+        csv
+    }
+}
+
+/// A set of textures that share the same pixel data.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DuplicateGroup {
+    /// The [`TextureMap::content_hash`] of the group, formatted as hex.
+    pub hash: String,
+    pub format: RenderFormat,
+    pub width: usize,
+    pub height: usize,
+    /// Identifiers (usually RRIDs) of every texture in the group.
+    pub members: Vec<String>,
+}
+
+/// Groups textures by their [`TextureMap::content_hash`].
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateIndex {
+    groups: HashMap<u64, DuplicateGroup>,
+    failures: Vec<(String, String)>,
+}
+
+impl DuplicateIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, id: impl Into<String>, texture: &TextureMap) {
+        let id = id.into();
+        match texture.content_hash() {
+            Ok(hash) => self
+                .groups
+                .entry(hash)
+                .or_insert_with(|| DuplicateGroup {
+                    hash: format!("{hash:016x}"),
+                    format: texture.format(),
+                    width: texture.width(),
+                    height: texture.height(),
+                    members: vec![],
+                })
+                .members
+                .push(id),
+            Err(e) => self.add_failure(id, e),
+        }
+    }
+
+    /// Records a resource that could not be parsed or hashed.
+    pub fn add_failure(&mut self, id: impl Into<String>, error: impl ToString) {
+        self.failures.push((id.into(), error.to_string()));
+    }
+
+    /// Combines two indices, this makes it possible to scan in parallel.
+    pub fn merge(mut self, other: DuplicateIndex) -> Self {
+        for (hash, group) in other.groups {
+            match self.groups.get_mut(&hash) {
+                Some(existing) => existing.members.extend(group.members),
+                None => {
+                    self.groups.insert(hash, group);
+                }
+            }
+        }
+        self.failures.extend(other.failures);
+        self.failures.sort();
+        self
+    }
+
+    /// The total amount of textures that were hashed.
+    pub fn total(&self) -> usize {
+        self.groups.values().map(|group| group.members.len()).sum()
+    }
+
+    /// The resources that failed to parse or hash, together with the error message.
+    pub fn failures(&self) -> &[(String, String)] {
+        &self.failures
+    }
+
+    /// Every group with more than one member, sorted from largest to smallest.
+    pub fn duplicates(&self) -> Vec<DuplicateGroup> {
+        let mut duplicates = self
+            .groups
+            .values()
+            .filter(|group| group.members.len() > 1)
+            .cloned()
+            .map(|mut group| {
+                group.members.sort();
+                group
+            })
+            .collect::<Vec<_>>();
+        duplicates.sort_by(|a, b| {
+            b.members
+                .len()
+                .cmp(&a.members.len())
+                .then(a.hash.cmp(&b.hash))
+        });
+        duplicates
+    }
+}
+
+/// Recursively collects every `.TEXT` file in a directory.
+pub fn find_texture_files<P: AsRef<Path>>(path: P) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut directories = vec![path.as_ref().to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                directories.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("text"))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Adds a single TEXT file to the histogram, the file stem is used as the example identifier.
+pub fn scan_file<P: AsRef<Path>>(
+    path: P,
+    woa_version: WoaVersion,
+    statistics: &mut TextureStatistics,
+) {
+    let path = path.as_ref();
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match TextureMap::from_file(path, woa_version) {
+        Ok(texture) => statistics.add(id, &texture),
+        Err(e) => statistics.add_failure(id, e),
+    }
+}
+
+/// Adds a single TEXT file to the duplicate index, the file stem is used as the identifier.
+/// The TEXD is read from the `.TEXD` file with the same name, if there is one.
+/// A texture that needs its TEXD but has none next to it is recorded as a failure, as its TEXT alone
+/// can't tell it apart from textures that only share the smaller mip levels.
+pub fn hash_file<P: AsRef<Path>>(path: P, woa_version: WoaVersion, index: &mut DuplicateIndex) {
+    let path = path.as_ref();
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let texture = TextureMap::from_file(path, woa_version).and_then(|mut texture| {
+        if let Some(texd_path) = find_texd_file(path) {
+            texture.set_mipblock1(MipblockData::from_file(texd_path, woa_version)?);
+        }
+        Ok(texture)
+    });
+    match texture {
+        Ok(texture) => index.add(id, &texture),
+        Err(e) => index.add_failure(id, e),
+    }
+}
+
+/// The TEXD next to a TEXT file, with the same name and a `.TEXD` extension in either case.
+fn find_texd_file(text_path: &Path) -> Option<PathBuf> {
+    ["TEXD", "texd"]
+        .into_iter()
+        .map(|extension| text_path.with_extension(extension))
+        .find(|path| path.is_file())
+}
+
+/// Adds every file to the duplicate index, see [`hash_file`].
+/// The files are hashed in parallel with the `rayon` feature, `hooks` may then be called from several threads.
+/// The files hashed before the cancellation stay in the index.
+pub fn hash_files<P: AsRef<Path> + Sync>(
+    files: &[P],
+    woa_version: WoaVersion,
+    index: &mut DuplicateIndex,
+    hooks: &ProgressHooks,
+) -> Result<(), Cancelled> {
+    let (found, result) = scan_items(
+        files,
+        hooks,
+        DuplicateIndex::new,
+        |index, file| hash_file(file, woa_version, index),
+        DuplicateIndex::merge,
+    );
+    *index = std::mem::take(index).merge(found);
+    result
+}
+
+/// Adds every file to the histogram, see [`scan_file`].
+/// The files are read in parallel with the `rayon` feature, `hooks` may then be called from several threads.
+/// The files scanned before the cancellation stay in the histogram.
+pub fn scan_files<P: AsRef<Path> + Sync>(
+    files: &[P],
+    woa_version: WoaVersion,
+    statistics: &mut TextureStatistics,
+    hooks: &ProgressHooks,
+) -> Result<(), Cancelled> {
+    let (found, result) = scan_items(
+        files,
+        hooks,
+        || statistics.empty(),
+        |statistics, file| scan_file(file, woa_version, statistics),
+        TextureStatistics::merge,
+    );
+    *statistics = std::mem::take(statistics).merge(found);
+    result
+}
+
+/// Groups every `.TEXT` file in a directory and its subdirectories by content hash.
+pub fn find_duplicates_in_directory<P: AsRef<Path>>(
+    path: P,
+    woa_version: WoaVersion,
+) -> io::Result<DuplicateIndex> {
+    find_duplicates_in_directory_with_hooks(path, woa_version, &ProgressHooks::default())
+}
+
+/// [`find_duplicates_in_directory`], reporting every file to `hooks`.
+/// Fails with [`io::ErrorKind::Interrupted`] once the scan is cancelled.
+pub fn find_duplicates_in_directory_with_hooks<P: AsRef<Path>>(
+    path: P,
+    woa_version: WoaVersion,
+    hooks: &ProgressHooks,
+) -> io::Result<DuplicateIndex> {
+    let mut index = DuplicateIndex::new();
+    hash_files(&find_texture_files(path)?, woa_version, &mut index, hooks)
+        .map_err(|e| io::Error::new(io::ErrorKind::Interrupted, e))?;
+    Ok(index)
+}
+
+/// Builds a histogram of every `.TEXT` file in a directory and its subdirectories.
+pub fn scan_directory<P: AsRef<Path>>(
+    path: P,
+    woa_version: WoaVersion,
+) -> io::Result<TextureStatistics> {
+    scan_directory_with_hooks(path, woa_version, &ProgressHooks::default())
+}
+
+/// [`scan_directory`], reporting every file to `hooks`.
+/// Fails with [`io::ErrorKind::Interrupted`] once the scan is cancelled.
+pub fn scan_directory_with_hooks<P: AsRef<Path>>(
+    path: P,
+    woa_version: WoaVersion,
+    hooks: &ProgressHooks,
+) -> io::Result<TextureStatistics> {
+    let mut statistics = TextureStatistics::default();
+    scan_files(
+        &find_texture_files(path)?,
+        woa_version,
+        &mut statistics,
+        hooks,
+    )
+    .map_err(|e| io::Error::new(io::ErrorKind::Interrupted, e))?;
+    Ok(statistics)
+}
+
+/// Visits every item, in parallel with the `rayon` feature.
+/// Every worker fills its own accumulator, these are merged once all items are visited.
+/// After a cancellation the remaining items are skipped, the accumulator keeps the visited ones.
+pub(crate) fn scan_items<T: Sync, A: Send>(
+    items: &[T],
+    hooks: &ProgressHooks,
+    init: impl Fn() -> A + Sync + Send,
+    visit: impl Fn(&mut A, &T) + Sync + Send,
+    #[cfg_attr(not(feature = "rayon"), allow(unused_variables))] merge: impl Fn(A, A) -> A + Sync + Send,
+) -> (A, Result<(), Cancelled>) {
+    let total = items.len();
+    let started = AtomicUsize::new(0);
+    let cancelled = AtomicBool::new(false);
+    let step = |mut accumulator: A, item: &T| {
+        let i = started.fetch_add(1, Ordering::Relaxed);
+        match hooks.report(Stage::Scan, i, total) {
+            Ok(()) => visit(&mut accumulator, item),
+            Err(Cancelled) => cancelled.store(true, Ordering::Relaxed),
+        }
+        accumulator
+    };
+
+    #[cfg(feature = "rayon")]
+    let accumulator = {
+        use rayon::prelude::*;
+        items.par_iter().fold(&init, step).reduce(&init, merge)
+    };
+    #[cfg(not(feature = "rayon"))]
+    let accumulator = items.iter().fold(init(), step);
+
+    if cancelled.load(Ordering::Relaxed) {
+        return (accumulator, Err(Cancelled));
+    }
+    (accumulator, hooks.report(Stage::Scan, total, total))
+}
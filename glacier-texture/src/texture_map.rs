@@ -1,20 +1,35 @@
 #![allow(unused_variables)]
 
 use crate::atlas::AtlasData;
+use crate::convert;
+use crate::convert::TextureConversionError;
 use crate::enums::*;
+use crate::hash::Fnv1a64;
+use crate::height;
 use crate::mipblock::MipblockData;
-use crate::pack::TexturePackerError;
+use crate::pack::TexturePackerError::PackingError;
+use crate::pack::{
+    assemble_texture_map, MipChain, MipLevels, TextureMapBuilder, TextureMapParameters,
+    TexturePackerError,
+};
+use crate::progress::ProgressHooks;
+use crate::tiling::{self, Tiling};
 use crate::WoaVersion;
 use binrw::helpers::until_eof;
 use binrw::{binread, binrw, BinRead, BinResult, BinWrite, BinWriterExt, Endian};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
+use std::hash::Hasher;
 use std::io::{BufReader, BufWriter, Cursor, Seek, Write};
 use std::path::Path;
 use std::{fs, io};
 
 /// Represents the maximum number of mip levels supported.
-const MAX_MIP_LEVELS: usize = 0xE;
+pub(crate) const MAX_MIP_LEVELS: usize = 0xE;
+
+/// The TEXD identifier of HM2016 and HM2 textures whose TEXT holds a downscaled part of the mip chain.
+/// HM3 dropped the field and always scales, so HM3 textures are treated as having this identifier.
+pub(crate) const SCALED_TEXD_IDENTIFIER: u32 = 0x4000;
 
 #[derive(Debug, thiserror::Error)]
 pub enum TextureMapError {
@@ -26,6 +41,15 @@ pub enum TextureMapError {
 
     #[error("Failed on {0}")]
     UnknownError(String),
+
+    #[error("Invalid header edit: {0}")]
+    InvalidHeaderEdit(String),
+
+    #[error("The texture stores {0} mip level(s) in its TEXD, which is not loaded")]
+    MissingTexd(usize),
+
+    #[error("The texture has {0} mip levels, but {1} of them belong in the TEXD")]
+    InvalidTextScale(usize, usize),
 }
 
 /// Arguments used for dynamically constructing texture map headers.
@@ -38,18 +62,51 @@ pub(crate) struct DynamicTextureMapArgs {
     pub(crate) text_mip_levels: u8,
 }
 
+/// Header values that are recalculated when packing, unless they were read and are packed losslessly.
+/// Fields a version doesn't have are left at 0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct PreservedFields {
+    pub(crate) num_textures: u16,
+    pub(crate) data_size: u32,
+    pub(crate) mips_interpolation_deprecated: u16,
+    pub(crate) atlas_data_size: u32,
+    pub(crate) atlas_data_offset: u32,
+    /// The unknown byte, scaling width, scaling height and mip count of the HM3 TEXT scaling data.
+    pub(crate) text_scaling: [u8; 4],
+    /// The padding after the HM3 TEXT scaling data.
+    pub(crate) padding: u32,
+}
+
 /// Trait that defines common functionality for texture map headers.
 pub(crate) trait TextureMapHeaderImpl {
     /// Calculates the texture scaling factor.
     fn text_scale(&self) -> usize;
     /// Returns the size of the texture map header.
     fn size() -> usize;
-    /// Calculates the size of the texture data.
-    fn text_data_size(&self) -> usize;
+    /// Calculates the size of the texture data, `None` if the mip table is inconsistent.
+    fn text_data_size(&self) -> Option<usize>;
     /// Indicates whether the texture has atlas data.
     fn has_atlas(&self) -> bool;
     /// Returns the number of mip levels in the texture.
     fn texd_mip_levels(&self) -> usize;
+    /// The header values that were read, `None` for built or edited textures.
+    fn preserved(&self) -> Option<&PreservedFields>;
+    /// The header values that are written when none are preserved.
+    fn calculated_fields(args: &DynamicTextureMapArgs) -> PreservedFields;
+}
+
+/// The size of the mips stored in the TEXT, calculated from the cumulative mip table.
+fn text_data_size(
+    mip_sizes: &[u32; MAX_MIP_LEVELS],
+    num_mip_levels: usize,
+    text_scale: usize,
+) -> Option<usize> {
+    let last_mip_size = *mip_sizes.get(num_mip_levels.checked_sub(1)?)? as usize;
+    if text_scale == 0 {
+        return Some(last_mip_size);
+    }
+    let texd_mip_size = *mip_sizes.get(text_scale - 1).unwrap_or(&0) as usize;
+    last_mip_size.checked_sub(texd_mip_size)
 }
 
 /// Texture map header for version 1 (HM2016).
@@ -58,10 +115,11 @@ pub(crate) trait TextureMapHeaderImpl {
 #[br(assert(
     num_textures == 1 && num_textures != 6, "Looks like you tried to export a cubemap texture, those are not supported yet"
 ))]
-#[bw(import(args: DynamicTextureMapArgs))]
+#[br(assert(num_mip_levels as usize <= MAX_MIP_LEVELS, "The texture has {} mip levels", num_mip_levels))]
+#[bw(import(fields: PreservedFields))]
 pub(crate) struct TextureMapHeaderV1 {
     #[br(temp)]
-    #[bw(calc(1))]
+    #[bw(calc(fields.num_textures))]
     num_textures: u16,
 
     pub(crate) type_: TextureType,
@@ -69,7 +127,7 @@ pub(crate) struct TextureMapHeaderV1 {
     pub(crate) texd_identifier: u32,
 
     #[br(temp)]
-    #[bw(calc(args.data_size - 8))]
+    #[bw(calc(fields.data_size))]
     data_size: u32,
     pub(crate) flags: TextureFlagsInner,
     pub(crate) width: u16,
@@ -80,21 +138,33 @@ pub(crate) struct TextureMapHeaderV1 {
     pub(crate) interpret_as: InterpretAs,
     pub(crate) dimensions: Dimensions,
     #[br(temp)]
-    #[bw(calc(0))]
+    #[bw(calc(fields.mips_interpolation_deprecated))]
     mips_interpolation_deprecated: u16,
 
     pub(crate) mip_sizes: [u32; MAX_MIP_LEVELS],
     #[br(temp)]
-    #[bw(calc(args.atlas_data_size))]
+    #[bw(calc(fields.atlas_data_size))]
     atlas_data_size: u32,
     #[br(temp)]
-    #[bw(calc(0x54))]
+    #[bw(calc(fields.atlas_data_offset))]
     atlas_data_offset: u32,
 
     //additional properties
     #[br(calc = atlas_data_size > 0)]
     #[bw(ignore)]
     pub(crate) has_atlas: bool,
+
+    #[br(calc = Some(PreservedFields {
+        num_textures,
+        data_size,
+        mips_interpolation_deprecated,
+        atlas_data_size,
+        atlas_data_offset,
+        ..Default::default()
+    }))]
+    #[bw(ignore)]
+    #[serde(skip)]
+    pub(crate) preserved: Option<PreservedFields>,
 }
 
 impl TextureMapHeaderImpl for TextureMapHeaderV1 {
@@ -117,15 +187,12 @@ impl TextureMapHeaderImpl for TextureMapHeaderV1 {
         92
     }
 
-    fn text_data_size(&self) -> usize {
-        let text_mip_levels = self.num_mip_levels as usize - self.text_scale();
-        let blocks_to_skip = self.num_mip_levels as usize - text_mip_levels;
-        let last_mip_size = self.mip_sizes[(self.num_mip_levels - 1) as usize] as usize;
-        if blocks_to_skip == 0 {
-            return last_mip_size;
-        }
-        let texd_mip_size = self.mip_sizes.get(blocks_to_skip - 1).unwrap_or(&0);
-        last_mip_size - *texd_mip_size as usize
+    fn text_data_size(&self) -> Option<usize> {
+        text_data_size(
+            &self.mip_sizes,
+            self.num_mip_levels as usize,
+            self.text_scale(),
+        )
     }
 
     fn has_atlas(&self) -> bool {
@@ -135,22 +202,37 @@ impl TextureMapHeaderImpl for TextureMapHeaderV1 {
     fn texd_mip_levels(&self) -> usize {
         self.num_mip_levels as usize
     }
+
+    fn preserved(&self) -> Option<&PreservedFields> {
+        self.preserved.as_ref()
+    }
+
+    fn calculated_fields(args: &DynamicTextureMapArgs) -> PreservedFields {
+        PreservedFields {
+            num_textures: 1,
+            data_size: args.data_size - 8,
+            atlas_data_size: args.atlas_data_size,
+            atlas_data_offset: 0x54,
+            ..Default::default()
+        }
+    }
 }
 
 #[binrw]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[br(assert(mip_sizes == compressed_mip_sizes))]
 #[br(assert(num_textures == 1))]
-#[bw(import(args: DynamicTextureMapArgs))]
+#[br(assert(num_mip_levels as usize <= MAX_MIP_LEVELS, "The texture has {} mip levels", num_mip_levels))]
+#[bw(import(fields: PreservedFields))]
 pub(crate) struct TextureMapHeaderV2 {
     #[br(temp)]
-    #[bw(calc(1))]
+    #[bw(calc(fields.num_textures))]
     num_textures: u16,
 
     pub(crate) type_: TextureType,
 
     #[br(temp)]
-    #[bw(calc(args.data_size))]
+    #[bw(calc(fields.data_size))]
     data_size: u32,
     pub(crate) flags: TextureFlagsInner,
     pub(crate) width: u16,
@@ -162,16 +244,27 @@ pub(crate) struct TextureMapHeaderV2 {
     pub(crate) mip_sizes: [u32; MAX_MIP_LEVELS],
     pub(crate) compressed_mip_sizes: [u32; MAX_MIP_LEVELS],
     #[br(temp)]
-    #[bw(calc(args.atlas_data_size))]
+    #[bw(calc(fields.atlas_data_size))]
     atlas_data_size: u32,
     #[br(temp)]
-    #[bw(calc(0x90))]
+    #[bw(calc(fields.atlas_data_offset))]
     atlas_data_offset: u32,
 
     //additional properties
     #[br(calc = atlas_data_size > 0)]
     #[bw(ignore)]
     pub(crate) has_atlas: bool,
+
+    #[br(calc = Some(PreservedFields {
+        num_textures,
+        data_size,
+        atlas_data_size,
+        atlas_data_offset,
+        ..Default::default()
+    }))]
+    #[bw(ignore)]
+    #[serde(skip)]
+    pub(crate) preserved: Option<PreservedFields>,
 }
 
 impl TextureMapHeaderImpl for TextureMapHeaderV2 {
@@ -189,7 +282,7 @@ impl TextureMapHeaderImpl for TextureMapHeaderV2 {
             return 1;
         }
 
-        if self.texd_identifier != 16384 {
+        if self.texd_identifier != SCALED_TEXD_IDENTIFIER {
             return 0;
         }
 
@@ -201,18 +294,12 @@ impl TextureMapHeaderImpl for TextureMapHeaderV2 {
         144
     }
 
-    fn text_data_size(&self) -> usize {
-        let text_mip_levels = self.num_mip_levels as usize - self.text_scale();
-        let blocks_to_skip = self.num_mip_levels as usize - text_mip_levels;
-        let last_mip_size = self.compressed_mip_sizes[(self.num_mip_levels - 1) as usize] as usize;
-        if blocks_to_skip == 0 {
-            return last_mip_size;
-        }
-        let texd_mip_size = self
-            .compressed_mip_sizes
-            .get(blocks_to_skip - 1)
-            .unwrap_or(&0);
-        last_mip_size - *texd_mip_size as usize
+    fn text_data_size(&self) -> Option<usize> {
+        text_data_size(
+            &self.compressed_mip_sizes,
+            self.num_mip_levels as usize,
+            self.text_scale(),
+        )
     }
 
     fn has_atlas(&self) -> bool {
@@ -222,23 +309,38 @@ impl TextureMapHeaderImpl for TextureMapHeaderV2 {
     fn texd_mip_levels(&self) -> usize {
         self.num_mip_levels as usize
     }
+
+    fn preserved(&self) -> Option<&PreservedFields> {
+        self.preserved.as_ref()
+    }
+
+    fn calculated_fields(args: &DynamicTextureMapArgs) -> PreservedFields {
+        PreservedFields {
+            num_textures: 1,
+            data_size: args.data_size,
+            atlas_data_size: args.atlas_data_size,
+            atlas_data_offset: 0x90,
+            ..Default::default()
+        }
+    }
 }
 
 #[binrw]
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[br(assert(text_scaling_width == num_mip_levels - text_mip_levels))]
-#[br(assert(text_scaling_height == num_mip_levels - text_mip_levels))]
+#[br(assert(num_mip_levels.checked_sub(text_mip_levels) == Some(text_scaling_width)))]
+#[br(assert(num_mip_levels.checked_sub(text_mip_levels) == Some(text_scaling_height)))]
 #[br(assert(num_textures == 1))]
-#[bw(import(args: DynamicTextureMapArgs))]
+#[br(assert(num_mip_levels as usize <= MAX_MIP_LEVELS, "The texture has {} mip levels", num_mip_levels))]
+#[bw(import(fields: PreservedFields))]
 pub(crate) struct TextureMapHeaderV3 {
     #[br(temp)]
-    #[bw(calc(1))]
+    #[bw(calc(fields.num_textures))]
     num_textures: u16,
 
     pub(crate) type_: TextureType,
 
     #[br(temp)]
-    #[bw(calc(args.data_size))]
+    #[bw(calc(fields.data_size))]
     data_size: u32,
     pub(crate) flags: TextureFlagsInner,
     pub(crate) width: u16,
@@ -250,35 +352,51 @@ pub(crate) struct TextureMapHeaderV3 {
     pub(crate) dimensions: Dimensions,
 
     #[br(temp)]
-    #[bw(calc(0))]
+    #[bw(calc(fields.mips_interpolation_deprecated))]
     mips_interpolation_deprecated: u16,
     pub(crate) mip_sizes: [u32; MAX_MIP_LEVELS],
     pub(crate) compressed_mip_sizes: [u32; MAX_MIP_LEVELS],
     #[br(temp)]
-    #[bw(calc(args.atlas_data_size))]
+    #[bw(calc(fields.atlas_data_size))]
     atlas_data_size: u32,
     #[br(temp)]
-    #[bw(calc(0x98))]
+    #[bw(calc(fields.atlas_data_offset))]
     atlas_data_offset: u32,
     #[br(temp)]
-    #[bw(calc(0xFF))]
+    #[bw(calc(fields.text_scaling[0]))]
     text_scaling_data1: u8,
     #[br(temp)]
-    #[bw(calc(args.text_scale))]
+    #[bw(calc(fields.text_scaling[1]))]
     text_scaling_width: u8,
     #[br(temp)]
-    #[bw(calc(args.text_scale))]
+    #[bw(calc(fields.text_scaling[2]))]
     text_scaling_height: u8,
 
     #[br(temp)]
-    #[bw(calc(args.text_mip_levels))]
-    #[brw(pad_after = 0x4)]
+    #[bw(calc(fields.text_scaling[3]))]
     text_mip_levels: u8,
 
+    #[br(temp)]
+    #[bw(calc(fields.padding))]
+    padding: u32,
+
     //additional properties
     #[br(calc = atlas_data_size > 0)]
     #[bw(ignore)]
     pub(crate) has_atlas: bool,
+
+    #[br(calc = Some(PreservedFields {
+        num_textures,
+        data_size,
+        mips_interpolation_deprecated,
+        atlas_data_size,
+        atlas_data_offset,
+        text_scaling: [text_scaling_data1, text_scaling_width, text_scaling_height, text_mip_levels],
+        padding,
+    }))]
+    #[bw(ignore)]
+    #[serde(skip)]
+    pub(crate) preserved: Option<PreservedFields>,
 }
 
 impl TextureMapHeaderImpl for TextureMapHeaderV3 {
@@ -308,18 +426,12 @@ impl TextureMapHeaderImpl for TextureMapHeaderV3 {
         152
     }
 
-    fn text_data_size(&self) -> usize {
-        let text_mip_levels = self.num_mip_levels as usize - self.text_scale();
-        let blocks_to_skip = self.num_mip_levels as usize - text_mip_levels;
-        let last_mip_size = self.compressed_mip_sizes[(self.num_mip_levels - 1) as usize] as usize;
-        if blocks_to_skip == 0 {
-            return last_mip_size;
-        }
-        let texd_mip_size = self
-            .compressed_mip_sizes
-            .get(blocks_to_skip - 1)
-            .unwrap_or(&0);
-        last_mip_size - *texd_mip_size as usize
+    fn text_data_size(&self) -> Option<usize> {
+        text_data_size(
+            &self.compressed_mip_sizes,
+            self.num_mip_levels as usize,
+            self.text_scale(),
+        )
     }
 
     fn has_atlas(&self) -> bool {
@@ -329,28 +441,46 @@ impl TextureMapHeaderImpl for TextureMapHeaderV3 {
     fn texd_mip_levels(&self) -> usize {
         self.num_mip_levels as usize
     }
+
+    fn preserved(&self) -> Option<&PreservedFields> {
+        self.preserved.as_ref()
+    }
+
+    fn calculated_fields(args: &DynamicTextureMapArgs) -> PreservedFields {
+        PreservedFields {
+            num_textures: 1,
+            data_size: args.data_size,
+            atlas_data_size: args.atlas_data_size,
+            atlas_data_offset: 0x98,
+            text_scaling: [0xFF, args.text_scale, args.text_scale, args.text_mip_levels],
+            ..Default::default()
+        }
+    }
 }
 
 #[binrw]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[br(import(woa_version: WoaVersion))]
+#[bw(import(lossless: bool))]
 pub struct TextureMap {
     #[br(args(woa_version))]
+    #[bw(args(lossless))]
     pub(crate) inner: TextureMapVersion,
 }
 
 #[binrw]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[br(import(woa_version: WoaVersion))]
+#[bw(import(lossless: bool))]
 pub(crate) enum TextureMapVersion {
     #[br(pre_assert(woa_version == WoaVersion::HM2016))]
-    V1(TextureMapInner<TextureMapHeaderV1>),
+    V1(#[bw(args(lossless))] TextureMapInner<TextureMapHeaderV1>),
 
     #[br(pre_assert(woa_version == WoaVersion::HM2))]
-    V2(TextureMapInner<TextureMapHeaderV2>),
+    V2(#[bw(args(lossless))] TextureMapInner<TextureMapHeaderV2>),
 
     #[br(pre_assert(woa_version == WoaVersion::HM3))]
-    V3(TextureMapInner<TextureMapHeaderV3>),
+    V3(#[bw(args(lossless))] TextureMapInner<TextureMapHeaderV3>),
 }
 
 impl From<TextureMapInner<TextureMapHeaderV1>> for TextureMap {
@@ -399,12 +529,18 @@ impl BinWrite for TextureData {
             TextureData::Tex(data) => writer.write_type(data, endian),
             TextureData::Mipblock1(mipblock) => {
                 let data = &mipblock.data;
-                let cut_data = &data
-                    .clone()
-                    .into_iter()
-                    .skip(data.len() - args.0)
-                    .collect::<Vec<_>>();
-                writer.write_type(cut_data, endian)
+                let start = data
+                    .len()
+                    .checked_sub(args.0)
+                    .ok_or(binrw::Error::AssertFail {
+                        pos: writer.stream_position()?,
+                        message: format!(
+                            "The mipblock holds {} bytes, but the TEXT needs {}",
+                            data.len(),
+                            args.0
+                        ),
+                    })?;
+                writer.write_type(&data[start..].to_vec(), endian)
             }
         }
     }
@@ -435,33 +571,36 @@ where
     #[br(parse_with = until_eof, map = TextureData::Tex)]
     #[serde(skip_serializing)]
     pub data: TextureData,
+
+    /// The data that was read from the TEXT, kept when a mipblock is set so it can be packed losslessly.
+    #[br(calc = None)]
+    #[serde(skip)]
+    pub(crate) original_text_data: Option<Vec<u8>>,
 }
 
 impl<A> BinWrite for TextureMapInner<A>
 where
-    A: for<'a> BinWrite<Args<'a> = (DynamicTextureMapArgs,)>
+    A: for<'a> BinWrite<Args<'a> = (PreservedFields,)>
         + Clone
         + for<'a> binrw::BinRead<Args<'a> = ()>,
     A: TextureMapHeaderImpl,
 {
-    type Args<'a> = ();
+    type Args<'a> = (bool,);
 
     fn write_options<W: Write + Seek>(
         &self,
         writer: &mut W,
         endian: Endian,
-        _: Self::Args<'_>,
+        (lossless,): Self::Args<'_>,
     ) -> BinResult<()> {
-        let atlas_size = self.atlas_data_size();
-        let total_size = self.data.size() + A::size() + atlas_size;
-
-        let args = DynamicTextureMapArgs {
-            data_size: total_size as u32,
-            atlas_data_size: atlas_size as u32,
-            text_scale: self.header.text_scale() as u8,
-            text_mip_levels: self.header.texd_mip_levels() as u8 - self.header.text_scale() as u8,
-        };
-        self.header.write_options(writer, endian, (args,))?;
+        let pos = writer.stream_position()?;
+        let fields = self
+            .stored_fields(lossless)
+            .map_err(|e| binrw::Error::AssertFail {
+                pos,
+                message: e.to_string(),
+            })?;
+        self.header.write_options(writer, endian, (fields,))?;
 
         // If atlas_data is present, write it
         if let Some(atlas_data) = &self.atlas_data {
@@ -469,7 +608,17 @@ where
         }
 
         // Now write the data
-        let text_data_size = self.header.text_data_size();
+        if let Some(original_text_data) = self.original_text_data.as_ref().filter(|_| lossless) {
+            writer.write_all(original_text_data)?;
+            return Ok(());
+        }
+        let text_data_size = self
+            .header
+            .text_data_size()
+            .ok_or(binrw::Error::AssertFail {
+                pos: writer.stream_position()?,
+                message: "The mip table of the texture is inconsistent".to_string(),
+            })?;
         self.data.write_options(writer, endian, (text_data_size,))?;
 
         Ok(())
@@ -480,9 +629,24 @@ impl<A> TextureMapInner<A>
 where
     A: for<'a> BinRead<Args<'a> = ()>,
     A: Clone,
-    A: for<'a> binrw::BinWrite<Args<'a> = (DynamicTextureMapArgs,)>,
+    A: for<'a> binrw::BinWrite<Args<'a> = (PreservedFields,)>,
     A: TextureMapHeaderImpl,
 {
+    /// Cuts the TEXT mips from the full chain, `None` if the mip table doesn't fit the data.
+    fn text_only(&self) -> Option<Self> {
+        let data = match &self.data {
+            TextureData::Tex(_) => return Some(self.clone()),
+            TextureData::Mipblock1(mipblock) => &mipblock.data,
+        };
+        let start = data.len().checked_sub(self.header.text_data_size()?)?;
+        Some(Self {
+            header: self.header.clone(),
+            atlas_data: self.atlas_data.clone(),
+            data: TextureData::Tex(data[start..].to_vec()),
+            original_text_data: None,
+        })
+    }
+
     pub fn data(&self) -> &Vec<u8> {
         match &self.data {
             TextureData::Tex(d) => d,
@@ -494,6 +658,29 @@ where
         &self.atlas_data
     }
 
+    /// The header values that are written when packing. With `lossless` set, the values that were read are kept.
+    pub(crate) fn stored_fields(&self, lossless: bool) -> Result<PreservedFields, TextureMapError> {
+        if let Some(preserved) = self.header.preserved().filter(|_| lossless) {
+            return Ok(preserved.clone());
+        }
+        let atlas_size = self.atlas_data_size();
+        let total_size = self.data.size() + A::size() + atlas_size;
+        let text_mip_levels = self
+            .header
+            .texd_mip_levels()
+            .checked_sub(self.header.text_scale())
+            .ok_or(TextureMapError::InvalidTextScale(
+                self.header.texd_mip_levels(),
+                self.header.text_scale(),
+            ))?;
+        Ok(A::calculated_fields(&DynamicTextureMapArgs {
+            data_size: total_size as u32,
+            atlas_data_size: atlas_size as u32,
+            text_scale: self.header.text_scale() as u8,
+            text_mip_levels: text_mip_levels as u8,
+        }))
+    }
+
     fn atlas_data_size(&self) -> usize {
         self.atlas_data
             .as_ref()
@@ -526,6 +713,16 @@ impl TextureMap {
         }
     }
 
+    /// The identifier of the TEXD resource. HM3 does not store this value, its textures get
+    /// [`SCALED_TEXD_IDENTIFIER`] so a port to an older version keeps the same TEXT scaling.
+    pub(crate) fn texd_identifier(&self) -> u32 {
+        match &self.inner {
+            TextureMapVersion::V1(tex) => tex.header.texd_identifier,
+            TextureMapVersion::V2(tex) => tex.header.texd_identifier,
+            TextureMapVersion::V3(_) => SCALED_TEXD_IDENTIFIER,
+        }
+    }
+
     pub fn version(&self) -> WoaVersion {
         match &self.inner {
             TextureMapVersion::V1(_) => WoaVersion::HM2016,
@@ -559,10 +756,10 @@ impl TextureMap {
     }
 
     fn text_mip_levels(&self) -> usize {
-        self.texd_mip_levels() - self.text_scale()
+        self.texd_mip_levels().saturating_sub(self.text_scale())
     }
 
-    fn texd_mip_levels(&self) -> usize {
+    pub(crate) fn texd_mip_levels(&self) -> usize {
         match &self.inner {
             TextureMapVersion::V1(inner) => inner.header.num_mip_levels as usize,
             TextureMapVersion::V2(inner) => inner.header.num_mip_levels as usize,
@@ -578,7 +775,8 @@ impl TextureMap {
         }
     }
 
-    fn text_scale(&self) -> usize {
+    /// The number of the largest mips that are stored in the TEXD instead of the TEXT.
+    pub fn text_scale(&self) -> usize {
         match &self.inner {
             TextureMapVersion::V1(tex) => tex.header.text_scale(),
             TextureMapVersion::V2(tex) => tex.header.text_scale(),
@@ -586,7 +784,7 @@ impl TextureMap {
         }
     }
 
-    fn mip_sizes(&self) -> Vec<u32> {
+    pub(crate) fn mip_sizes(&self) -> Vec<u32> {
         match &self.inner {
             TextureMapVersion::V1(tex) => tex
                 .header
@@ -612,7 +810,7 @@ impl TextureMap {
         }
     }
 
-    fn compressed_mip_sizes(&self) -> Vec<u32> {
+    pub(crate) fn compressed_mip_sizes(&self) -> Vec<u32> {
         match &self.inner {
             TextureMapVersion::V1(tex) => tex
                 .header
@@ -649,9 +847,9 @@ impl TextureMap {
             WoaVersion::HM3 => {
                 if self.has_mipblock1() {
                     //if texture has a TEXD
-                    (self.mip_sizes().first().cloned().unwrap_or(0)
-                        + self.mip_sizes().get(1).cloned().unwrap_or(0))
-                        as usize //the size of the largest two TEXD mip
+                    self.mip_sizes().first().cloned().unwrap_or(0) as usize
+                        + self.mip_sizes().get(1).cloned().unwrap_or(0) as usize
+                //the size of the largest two TEXD mip
                 } else {
                     0
                 }
@@ -672,7 +870,7 @@ impl TextureMap {
             .flatten()
     }
 
-    fn texd_size(&self) -> (usize, usize) {
+    pub(crate) fn texd_size(&self) -> (usize, usize) {
         match &self.inner {
             TextureMapVersion::V1(tex) => (tex.header.width as usize, tex.header.height as usize),
             TextureMapVersion::V2(tex) => (tex.header.width as usize, tex.header.height as usize),
@@ -762,12 +960,31 @@ impl TextureMap {
     ) -> Result<Self, TextureMapError> {
         let file = File::open(path).map_err(TextureMapError::IoError)?;
         let mut reader = BufReader::new(file);
-        TextureMap::read_le_args(&mut reader, (woa_version,)).map_err(TextureMapError::ParsingError)
+        let texture = TextureMap::read_le_args(&mut reader, (woa_version,))
+            .map_err(TextureMapError::ParsingError)?;
+        texture.validate()?;
+        Ok(texture)
     }
 
     pub fn from_memory(data: &[u8], woa_version: WoaVersion) -> Result<Self, TextureMapError> {
         let mut reader = Cursor::new(data);
-        TextureMap::read_le_args(&mut reader, (woa_version,)).map_err(TextureMapError::ParsingError)
+        let texture = TextureMap::read_le_args(&mut reader, (woa_version,))
+            .map_err(TextureMapError::ParsingError)?;
+        texture.validate()?;
+        Ok(texture)
+    }
+
+    /// Checks that the header leaves at least one mip in the TEXT after the TEXD mips are taken off.
+    /// The split follows the formula of the game, a header with fewer mips than its area implies can't be stored.
+    pub(crate) fn validate(&self) -> Result<(), TextureMapError> {
+        let text_scale = self.text_scale();
+        if text_scale > 0 && text_scale >= self.texd_mip_levels() {
+            return Err(TextureMapError::InvalidTextScale(
+                self.texd_mip_levels(),
+                text_scale,
+            ));
+        }
+        Ok(())
     }
 
     pub fn default_mipmap(&self) -> Result<MipLevel, TextureMapError> {
@@ -778,6 +995,22 @@ impl TextureMap {
         (0..self.num_mip_levels()).map(move |level| self.mipmap(level))
     }
 
+    /// Reads every mip level up front, ordered from the largest to the smallest level.
+    /// With the `rayon` feature the levels are decompressed in parallel.
+    pub fn all_mipmaps(&self) -> Vec<Result<MipLevel, TextureMapError>> {
+        #[cfg(feature = "rayon")]
+        let mips = {
+            use rayon::prelude::*;
+            (0..self.num_mip_levels())
+                .into_par_iter()
+                .map(|level| self.mipmap(level))
+                .collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let mips = self.mipmaps().collect();
+        mips
+    }
+
     pub fn mipmap(&self, level: usize) -> Result<MipLevel, TextureMapError> {
         let removed_mip_count = self.texd_mip_levels() - self.text_mip_levels();
 
@@ -785,55 +1018,29 @@ impl TextureMap {
         let mut block_sizes: Vec<u32> = self.compressed_mip_sizes();
 
         if !self.has_mipblock1() {
-            let removed_mip = mips_sizes
-                .drain(0..removed_mip_count)
-                .collect::<Vec<u32>>()
-                .pop()
-                .unwrap_or(0);
-            mips_sizes.iter_mut().for_each(|x| {
-                if *x > 0 {
-                    *x -= removed_mip
-                }
-            });
-
-            let removed_block = block_sizes
-                .drain(0..removed_mip_count)
-                .collect::<Vec<u32>>()
-                .pop()
-                .unwrap_or(0);
-            block_sizes.iter_mut().for_each(|x| {
-                if *x > 0 {
-                    *x -= removed_block
-                }
-            });
+            mips_sizes = Self::drop_texd_mips(mips_sizes, removed_mip_count)?;
+            block_sizes = Self::drop_texd_mips(block_sizes, removed_mip_count)?;
         }
 
-        if level > self.texd_mip_levels() {
-            return Err(TextureMapError::UnknownError(
-                "mip level is out of bounds".parse().unwrap(),
-            ));
-        }
-
-        let mip_start = if level > 0 { mips_sizes[level - 1] } else { 0 };
-        let mip_size = mips_sizes.get(level).ok_or(TextureMapError::UnknownError(
-            "mip level is out of bounds".parse().unwrap(),
-        ))? - mip_start;
-
-        let block_start = if level > 0 { block_sizes[level - 1] } else { 0 };
-        let block_size = block_sizes.get(level).ok_or(TextureMapError::UnknownError(
-            "mip level is out of bounds".parse().unwrap(),
-        ))? - block_start;
+        let (_, mip_size) = Self::mip_range(&mips_sizes, level)?;
+        let (block_start, block_size) = Self::mip_range(&block_sizes, level)?;
 
         let is_compressed = mip_size != block_size;
         let block = self
             .data()
-            .clone()
-            .into_iter()
-            .skip(block_start as usize)
-            .take(block_size as usize)
-            .collect::<Vec<u8>>();
+            .get(block_start..block_start + block_size)
+            .ok_or(TextureMapError::UnknownError(format!(
+                "mip level {level} lies outside of the texture data"
+            )))?
+            .to_vec();
         let data = if is_compressed {
-            let mut dst = vec![0u8; mip_size as usize];
+            // LZ4 can't expand a block more than 255 times, this guards against absurd allocations
+            if mip_size > block_size * 255 + 16 {
+                return Err(TextureMapError::UnknownError(format!(
+                    "mip level {level} claims to decompress {block_size} bytes to {mip_size} bytes"
+                )));
+            }
+            let mut dst = vec![0u8; mip_size];
             match lz4::block::decompress_to_buffer(
                 block.as_slice(),
                 Some(mip_size as i32),
@@ -851,22 +1058,473 @@ impl TextureMap {
             block
         };
 
+        let width = self.width() >> level;
+        let height = self.height() >> level;
+        let tiling = Tiling::from_flags(self.flags().inner);
+        let data = tiling::untile(tiling, self.format(), width, height, &data).map_err(|e| {
+            TextureMapError::UnknownError(format!("Failed to untile mip level {level}: {e}"))
+        })?;
+
         Ok(MipLevel {
             format: self.format(),
-            width: self.width() >> level,
-            height: self.height() >> level,
+            width,
+            height,
             data,
         })
     }
 
+    /// Removes the mips that are stored in the TEXD from a cumulative mip table.
+    fn drop_texd_mips(mut sizes: Vec<u32>, count: usize) -> Result<Vec<u32>, TextureMapError> {
+        let removed = sizes
+            .drain(0..count.min(sizes.len()))
+            .next_back()
+            .unwrap_or(0);
+        sizes
+            .into_iter()
+            .map(|size| {
+                size.checked_sub(removed)
+                    .ok_or(TextureMapError::UnknownError(
+                        "the mip table is not cumulative".to_string(),
+                    ))
+            })
+            .collect()
+    }
+
+    /// The offset and size of a mip level in a cumulative mip table.
+    fn mip_range(sizes: &[u32], level: usize) -> Result<(usize, usize), TextureMapError> {
+        let out_of_bounds =
+            || TextureMapError::UnknownError("mip level is out of bounds".to_string());
+        let start = match level {
+            0 => 0,
+            _ => *sizes.get(level - 1).ok_or_else(out_of_bounds)?,
+        };
+        let end = *sizes.get(level).ok_or_else(out_of_bounds)?;
+        let size = end.checked_sub(start).ok_or(TextureMapError::UnknownError(
+            "the mip table is not cumulative".to_string(),
+        ))?;
+        Ok((start as usize, size as usize))
+    }
+
     pub fn has_atlas(&self) -> bool {
         self.atlas().is_some()
     }
 
+    /// A stable hash of the pixel data, independent of the game version and its LZ4 packing.
+    /// The format and the full mip chain are hashed. A texture that keeps some of its mips in a TEXD
+    /// can only be hashed with that TEXD loaded, otherwise [`TextureMapError::MissingTexd`] is returned.
+    /// That way the hash doesn't depend on whether the TEXD was loaded.
+    pub fn content_hash(&self) -> Result<u64, TextureMapError> {
+        if !self.has_mipblock1() && self.text_scale() > 0 {
+            return Err(TextureMapError::MissingTexd(self.text_scale()));
+        }
+
+        let mut hasher = Fnv1a64::default();
+        hasher.write(&(self.format() as u16).to_le_bytes());
+        for mip in self.all_mipmaps() {
+            let mip = mip?;
+            hasher.write(&(mip.width as u32).to_le_bytes());
+            hasher.write(&(mip.height as u32).to_le_bytes());
+            hasher.write(&mip.data);
+        }
+        Ok(hasher.finish())
+    }
+
+    /// Returns a copy of this texture with every mip level converted to `format`.
+    /// Each existing level is converted on its own, so no mip levels are regenerated.
+    /// All other header values, flags and the atlas are kept as-is.
+    ///
+    /// The header describes the full mip chain, so a texture that keeps some of its mips in a TEXD
+    /// can only be transcoded with that TEXD loaded. Without it, [`TexturePackerError::MissingTexd`]
+    /// is returned.
+    pub fn transcoded(&self, format: RenderFormat) -> Result<TextureMap, TexturePackerError> {
+        if format == self.format() {
+            return Ok(self.clone());
+        }
+
+        if !self.has_mipblock1() && self.text_scale() > 0 {
+            return Err(TexturePackerError::MissingTexd(self.text_scale()));
+        }
+
+        let mips = (0..self.num_mip_levels())
+            .map(|level| {
+                TextureMapBuilder::from_texture_map_mip(self, level)?
+                    .with_format(format)
+                    .encode_source_level()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut params = TextureMapParameters::from_texture_map_header(self);
+        params.set_format(format);
+
+        assemble_texture_map(
+            self.version(),
+            &params,
+            self.atlas().clone(),
+            self.has_mipblock1(),
+            MipChain {
+                width: self.width() as u16,
+                height: self.height() as u16,
+                mips,
+            },
+            &ProgressHooks::default(),
+        )
+    }
+
+    /// Returns the TEXT-only variant of a texture that holds its full mip chain.
+    /// Only the mips the header assigns to the TEXT are kept, so the result is a valid low resolution
+    /// texture without a TEXD. Billboards, volumes, `UNKNOWN512` textures and single mip textures keep
+    /// every mip, a 4x4 BC1 texture drops its largest mip.
+    /// A texture without a TEXD is returned as-is.
+    pub fn text_only(&self) -> Result<TextureMap, TexturePackerError> {
+        let inner = match &self.inner {
+            TextureMapVersion::V1(tex) => tex.text_only().map(TextureMapVersion::V1),
+            TextureMapVersion::V2(tex) => tex.text_only().map(TextureMapVersion::V2),
+            TextureMapVersion::V3(tex) => tex.text_only().map(TextureMapVersion::V3),
+        }
+        .ok_or(PackingError(
+            "The mip table of the texture doesn't match its data".to_string(),
+        ))?;
+        Ok(TextureMap { inner })
+    }
+
+    /// Returns a copy of this texture laid out for another game version. The encoded mip data is reused as-is,
+    /// so nothing is re-encoded and all header values, flags and the atlas are kept.
+    /// Only the World of Assassination versions can be ported between, Glacier 1 textures aren't supported.
+    pub fn ported(&self, woa_version: WoaVersion) -> Result<TextureMap, TexturePackerError> {
+        if !self.has_mipblock1() && self.text_scale() > 0 {
+            return Err(PackingError(
+                "Porting a texture without its TEXD would change its dimensions".to_string(),
+            ));
+        }
+
+        let mips = self
+            .all_mipmaps()
+            .into_iter()
+            .map(|mip| mip.map(|mip| mip.data))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| PackingError(format!("Failed to read the mip levels: {e}")))?;
+
+        assemble_texture_map(
+            woa_version,
+            &TextureMapParameters::from_texture_map_header(self),
+            self.atlas().clone(),
+            self.has_mipblock1(),
+            MipChain {
+                width: self.width() as u16,
+                height: self.height() as u16,
+                mips,
+            },
+            &ProgressHooks::default(),
+        )
+    }
+
+    /// Creates an RGBA8 preview whose largest side is at most `max_size`.
+    /// The smallest mip level at or above that size is decoded, so only the TEXT data is needed
+    /// when the TEXD isn't loaded. Height maps are shown in grayscale like [`height::create_height_png`],
+    /// normal maps get their blue channel reconstructed and other single channel textures are shown in grayscale.
+    pub fn thumbnail(&self, max_size: usize) -> Result<MipLevel, TextureConversionError> {
+        if max_size == 0 {
+            return Err(TextureConversionError::InvalidTexture(
+                "The thumbnail size has to be larger than 0".to_string(),
+            ));
+        }
+
+        let level = (0..self.num_mip_levels())
+            .rev()
+            .find(|level| {
+                let (width, height) = (self.width() >> level, self.height() >> level);
+                width.max(height) >= max_size
+            })
+            .unwrap_or(0);
+
+        let is_height_map = self.is_height_map();
+        let mut mip = if is_height_map {
+            height::decode_height_rgba8(self, level)?
+        } else {
+            convert::decode_mipmap(self, level)?
+        };
+        let largest = mip.width.max(mip.height);
+        if largest > max_size {
+            let width = (mip.width * max_size / largest).max(1);
+            let height = (mip.height * max_size / largest).max(1);
+            mip = convert::resize_rgba8(mip, width, height)?;
+        }
+
+        if is_height_map {
+            return Ok(mip);
+        }
+
+        let format = self.format();
+        let pixels = mip.data.chunks_exact_mut(4);
+        match (self.texture_type(), format.num_channels()) {
+            (TextureType::Normal | TextureType::CompoundNormal, 2) => {
+                for pixel in pixels {
+                    let x = pixel[0] as f32 / 127.5 - 1.0;
+                    let y = pixel[1] as f32 / 127.5 - 1.0;
+                    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+                    pixel[2] = ((z + 1.0) * 127.5) as u8;
+                    pixel[3] = 0xFF;
+                }
+            }
+            (_, 1) => {
+                let channel = if format == RenderFormat::A8 { 3 } else { 0 };
+                for pixel in pixels {
+                    let value = pixel[channel];
+                    pixel.copy_from_slice(&[value, value, value, 0xFF]);
+                }
+            }
+            _ => {}
+        }
+        Ok(mip)
+    }
+
+    /// Returns a copy of this texture whose largest side is at most `max_dimension`.
+    /// When the requested resolution is part of the mip chain, the top mip levels are dropped and the
+    /// encoded data is reused as-is. Otherwise, the texture is decoded, resized and encoded again.
+    /// The header and TEXD layout are recalculated for the version of this texture.
+    ///
+    /// The atlas coordinates are relative to the texture size and are kept as-is. Resizing fails when
+    /// the atlas tiles would no longer line up with the texels of the smaller texture.
+    ///
+    /// A texture without its TEXD stays TEXT-only, so the mips the new header assigns to the TEXD are
+    /// dropped as well. E.g. an 8x8 BC1 texture resized to 4 holds a 2x2 texture, as the TEXT of a
+    /// 4x4 BC1 texture skips its largest mip.
+    pub fn resized(&self, max_dimension: usize) -> Result<TextureMap, TexturePackerError> {
+        if max_dimension == 0 {
+            return Err(PackingError(
+                "The maximum dimension has to be larger than 0".to_string(),
+            ));
+        }
+
+        let largest = self.width().max(self.height());
+        let mut dropped_levels = 0;
+        while (largest >> dropped_levels) > max_dimension {
+            dropped_levels += 1;
+        }
+        if dropped_levels == 0 {
+            return Ok(self.clone());
+        }
+
+        let width = (self.width() >> dropped_levels).max(1);
+        let height = (self.height() >> dropped_levels).max(1);
+
+        if let Some(atlas) = self.atlas() {
+            if !atlas.keeps_texel_alignment((self.width(), self.height()), (width, height)) {
+                return Err(PackingError(format!(
+                    "The atlas tiles don't line up with the texels of a {width}x{height} texture"
+                )));
+            }
+        }
+
+        let chain = if dropped_levels < self.num_mip_levels() {
+            MipChain {
+                width: width as u16,
+                height: height as u16,
+                mips: self
+                    .all_mipmaps()
+                    .into_iter()
+                    .skip(dropped_levels)
+                    .map(|mip| mip.map(|mip| mip.data))
+                    .collect::<Result<Vec<_>, _>>()?,
+            }
+        } else {
+            let max_levels = (usize::BITS - width.max(height).leading_zeros()) as usize;
+            let levels = self.num_mip_levels().clamp(1, max_levels);
+            TextureMapBuilder::from_texture_map_mip(self, 0)?
+                .with_num_mip_levels(MipLevels::Limit(levels as u8))
+                .resize_source(width, height)?
+                .encode_mip_chain()?
+        };
+
+        let mut params = TextureMapParameters::from_texture_map_header(self);
+        let last_level = chain.mips.len().saturating_sub(1);
+        params.set_default_mip_level(
+            (self.default_mip_level() as usize)
+                .saturating_sub(dropped_levels)
+                .min(last_level) as u8,
+        );
+
+        let resized = assemble_texture_map(
+            self.version(),
+            &params,
+            self.atlas().clone(),
+            true,
+            chain,
+            &ProgressHooks::default(),
+        )?;
+        if self.has_mipblock1() {
+            Ok(resized)
+        } else {
+            resized.text_only()
+        }
+    }
+
+    /// Sets the full mip chain read from the TEXD. The TEXT data that was read is kept for lossless packing.
+    /// Replacing an earlier mipblock drops that data and the preserved header values, as they no longer match.
     pub fn set_mipblock1(&mut self, mipblock: MipblockData) {
+        fn keep_text_data<A>(inner: &mut TextureMapInner<A>)
+        where
+            A: for<'a> BinRead<Args<'a> = ()> + TextureMapHeaderImpl,
+        {
+            match &inner.data {
+                TextureData::Tex(data) => {
+                    inner.original_text_data.get_or_insert_with(|| data.clone());
+                }
+                TextureData::Mipblock1(_) => inner.original_text_data = None,
+            }
+        }
+        let replaces_mipblock = self.has_mipblock1();
+        match &mut self.inner {
+            TextureMapVersion::V1(t) => {
+                keep_text_data(t);
+                if replaces_mipblock {
+                    t.header.preserved = None;
+                }
+            }
+            TextureMapVersion::V2(t) => {
+                keep_text_data(t);
+                if replaces_mipblock {
+                    t.header.preserved = None;
+                }
+            }
+            TextureMapVersion::V3(t) => {
+                keep_text_data(t);
+                if replaces_mipblock {
+                    t.header.preserved = None;
+                }
+            }
+        }
         self.set_data(TextureData::Mipblock1(mipblock))
     }
 
+    /// Applies a metadata edit to a copy of the header and only keeps it if the TEXT/TEXD split stays the same,
+    /// the mip data of the texture would no longer match its header otherwise.
+    fn edit_header<A>(header: &mut A, edit: impl FnOnce(&mut A)) -> Result<(), TextureMapError>
+    where
+        A: TextureMapHeaderImpl + Clone,
+    {
+        let mut edited = header.clone();
+        edit(&mut edited);
+        if edited.text_scale() != header.text_scale() {
+            return Err(TextureMapError::InvalidHeaderEdit(format!(
+                "the edit changes the number of mips stored in the TEXD from {} to {}, rebuild the texture instead",
+                header.text_scale(),
+                edited.text_scale()
+            )));
+        }
+        *header = edited;
+        Ok(())
+    }
+
+    /// Sets the mip level the game loads by default.
+    pub fn set_default_mip_level(&mut self, default_mip_level: u8) -> Result<(), TextureMapError> {
+        if default_mip_level as usize >= self.texd_mip_levels() {
+            return Err(TextureMapError::InvalidHeaderEdit(format!(
+                "the default mip level {default_mip_level} is out of range, the texture has {} mip levels",
+                self.texd_mip_levels()
+            )));
+        }
+        match &mut self.inner {
+            TextureMapVersion::V1(tex) => tex.header.default_mip_level = default_mip_level,
+            TextureMapVersion::V2(tex) => {
+                if default_mip_level == 0 {
+                    //H2 crashes with index 0
+                    return Err(TextureMapError::InvalidHeaderEdit(
+                        "HM2 does not support a default mip level of 0".to_string(),
+                    ));
+                }
+                tex.header.default_mip_level = default_mip_level
+            }
+            TextureMapVersion::V3(tex) => tex.header.default_mip_level = default_mip_level,
+        }
+        Ok(())
+    }
+
+    /// Sets the texture type, fails if the new type changes which mips are stored in the TEXD.
+    pub fn set_texture_type(&mut self, texture_type: TextureType) -> Result<(), TextureMapError> {
+        match &mut self.inner {
+            TextureMapVersion::V1(tex) => {
+                Self::edit_header(&mut tex.header, |h| h.type_ = texture_type)
+            }
+            TextureMapVersion::V2(tex) => {
+                Self::edit_header(&mut tex.header, |h| h.type_ = texture_type)
+            }
+            TextureMapVersion::V3(tex) => {
+                Self::edit_header(&mut tex.header, |h| h.type_ = texture_type)
+            }
+        }
+    }
+
+    /// Sets how the texture is interpreted, fails if the new value changes which mips are stored in the TEXD.
+    /// HM2 textures don't store this value.
+    pub fn set_interpret_as(&mut self, interpret_as: InterpretAs) -> Result<(), TextureMapError> {
+        match &mut self.inner {
+            TextureMapVersion::V1(tex) => {
+                Self::edit_header(&mut tex.header, |h| h.interpret_as = interpret_as)
+            }
+            TextureMapVersion::V2(_) => Err(TextureMapError::InvalidHeaderEdit(
+                "HM2 textures have no interpret_as field".to_string(),
+            )),
+            TextureMapVersion::V3(tex) => {
+                Self::edit_header(&mut tex.header, |h| h.interpret_as = interpret_as)
+            }
+        }
+    }
+
+    /// Replaces the texture flags. The atlas flag has to match whether the texture has atlas data,
+    /// use [`TextureMap::set_atlas`] to add or remove an atlas.
+    /// The swizzled and Xbox 360 flags decide how the stored mips are tiled, so they can't be changed.
+    pub fn set_flags(&mut self, flags: TextureFlags) -> Result<(), TextureMapError> {
+        if flags.atlas() != self.has_atlas() {
+            return Err(TextureMapError::InvalidHeaderEdit(format!(
+                "the atlas flag is {}, but the texture {} atlas data",
+                flags.atlas(),
+                if self.has_atlas() { "has" } else { "has no" }
+            )));
+        }
+        let current = self.flags().inner;
+        if flags.inner.swizzled() != current.swizzled()
+            || flags.inner.memory_read_xbox_360() != current.memory_read_xbox_360()
+        {
+            return Err(TextureMapError::InvalidHeaderEdit(
+                "the swizzled and Xbox 360 flags describe the layout of the stored mips"
+                    .to_string(),
+            ));
+        }
+        match &mut self.inner {
+            TextureMapVersion::V1(tex) => tex.header.flags = flags.inner,
+            TextureMapVersion::V2(tex) => tex.header.flags = flags.inner,
+            TextureMapVersion::V3(tex) => tex.header.flags = flags.inner,
+        }
+        Ok(())
+    }
+
+    /// Replaces or removes the atlas data and updates the atlas flag to match.
+    /// This changes the header layout, so the preserved header values used by lossless packing are dropped.
+    pub fn set_atlas(&mut self, atlas_data: Option<AtlasData>) {
+        let has_atlas = atlas_data.is_some();
+        match &mut self.inner {
+            TextureMapVersion::V1(tex) => {
+                tex.header.has_atlas = has_atlas;
+                tex.header.flags = tex.header.flags.with_atlas(has_atlas);
+                tex.header.preserved = None;
+                tex.atlas_data = atlas_data;
+            }
+            TextureMapVersion::V2(tex) => {
+                tex.header.has_atlas = has_atlas;
+                tex.header.flags = tex.header.flags.with_atlas(has_atlas);
+                tex.header.preserved = None;
+                tex.atlas_data = atlas_data;
+            }
+            TextureMapVersion::V3(tex) => {
+                tex.header.has_atlas = has_atlas;
+                tex.header.flags = tex.header.flags.with_atlas(has_atlas);
+                tex.header.preserved = None;
+                tex.atlas_data = atlas_data;
+            }
+        }
+    }
+
     fn texd_header(&self) -> Result<Vec<u8>, TextureMapError> {
         let mut writer = Cursor::new(Vec::new());
 
@@ -895,18 +1553,21 @@ impl TextureMap {
         };
 
         match &self.inner {
-            TextureMapVersion::V1(tex) => {
-                tex.header
-                    .write_options(&mut writer, Endian::Little, (args,))?
-            }
-            TextureMapVersion::V2(tex) => {
-                tex.header
-                    .write_options(&mut writer, Endian::Little, (args,))?
-            }
-            TextureMapVersion::V3(tex) => {
-                tex.header
-                    .write_options(&mut writer, Endian::Little, (args,))?
-            }
+            TextureMapVersion::V1(tex) => tex.header.write_options(
+                &mut writer,
+                Endian::Little,
+                (TextureMapHeaderV1::calculated_fields(&args),),
+            )?,
+            TextureMapVersion::V2(tex) => tex.header.write_options(
+                &mut writer,
+                Endian::Little,
+                (TextureMapHeaderV2::calculated_fields(&args),),
+            )?,
+            TextureMapVersion::V3(tex) => tex.header.write_options(
+                &mut writer,
+                Endian::Little,
+                (TextureMapHeaderV3::calculated_fields(&args),),
+            )?,
         }
 
         // If atlas_data is present, write it
@@ -919,20 +1580,55 @@ impl TextureMap {
 
     pub fn pack_to_vec(&self) -> Result<Vec<u8>, TexturePackerError> {
         let mut writer = Cursor::new(Vec::new());
-        self.pack_internal(&mut writer)?;
+        self.pack_internal(&mut writer, false)?;
         Ok(writer.into_inner())
     }
 
     pub fn pack_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), TexturePackerError> {
         let file = fs::File::create(path).map_err(TexturePackerError::IoError)?;
         let mut writer = BufWriter::new(file);
-        self.pack_internal(&mut writer)?;
+        self.pack_internal(&mut writer, false)?;
         Ok(())
     }
 
-    fn pack_internal<W: Write + Seek>(&self, writer: &mut W) -> Result<(), TexturePackerError> {
-        self.write_le_args(writer, ())
+    /// Packs the texture while keeping every header value, padding byte and the original TEXT data
+    /// as they were read. An unmodified texture packs to the exact bytes it was read from.
+    pub fn pack_to_vec_lossless(&self) -> Result<Vec<u8>, TexturePackerError> {
+        let mut writer = Cursor::new(Vec::new());
+        self.pack_internal(&mut writer, true)?;
+        Ok(writer.into_inner())
+    }
+
+    /// See [`TextureMap::pack_to_vec_lossless`].
+    pub fn pack_to_file_lossless<P: AsRef<Path>>(&self, path: P) -> Result<(), TexturePackerError> {
+        let file = fs::File::create(path).map_err(TexturePackerError::IoError)?;
+        let mut writer = BufWriter::new(file);
+        self.pack_internal(&mut writer, true)?;
+        Ok(())
+    }
+
+    /// Packs the texture losslessly and compares it against the bytes it was read from.
+    /// Returns the offset of the first byte that differs, `None` if the output is identical.
+    pub fn verify_lossless(&self, original: &[u8]) -> Result<Option<usize>, TexturePackerError> {
+        Ok(first_difference(&self.pack_to_vec_lossless()?, original))
+    }
+
+    fn pack_internal<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        lossless: bool,
+    ) -> Result<(), TexturePackerError> {
+        self.write_le_args(writer, (lossless,))
             .map_err(TexturePackerError::SerializationError)?;
         Ok(())
     }
 }
+
+/// The offset of the first byte that differs between two buffers, `None` if they are identical.
+/// When one buffer is a prefix of the other, the length of the shorter one is returned.
+pub fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(a, b)| a != b)
+        .or((a.len() != b.len()).then(|| a.len().min(b.len())))
+}
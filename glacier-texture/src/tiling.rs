@@ -0,0 +1,262 @@
+//! Console memory layouts of texture data, selected by the texture flags.
+//!
+//! Textures from console builds can store their mips tiled instead of row by row:
+//! * `memory_read_xbox_360`: the Xbox 360 GPU layout, every mip is a surface padded to 32x32 blocks
+//!   and whole 4 KiB pages, stored as big-endian words. Packed mip tails are not supported, every mip is treated as its own surface.
+//! * `swizzled`: a Morton (Z-order) layout of the blocks, which requires power-of-two dimensions.
+//!   This layout hasn't been checked against console data yet, so it is only used with the `unstable` feature.
+//!   Without it, swizzled textures are read and written as-is.
+//!
+//! Untiling always produces the linear layout used by the PC builds, tiling is its exact inverse.
+
+use crate::enums::{RenderFormat, TextureFlagsInner};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Tiling {
+    Linear,
+    Xbox360,
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+    Morton,
+}
+
+impl Tiling {
+    /// The layout the flags refer to, the Xbox 360 layout takes precedence over the swizzled flag.
+    pub(crate) fn from_flags(flags: TextureFlagsInner) -> Self {
+        if flags.memory_read_xbox_360() {
+            Tiling::Xbox360
+        } else if cfg!(feature = "unstable") && flags.swizzled() {
+            Tiling::Morton
+        } else {
+            Tiling::Linear
+        }
+    }
+}
+
+/// The dimensions of a mip in blocks, or in pixels for uncompressed formats.
+fn block_dimensions(format: RenderFormat, width: usize, height: usize) -> (usize, usize) {
+    if format.is_compressed() {
+        (width.div_ceil(4).max(1), height.div_ceil(4).max(1))
+    } else {
+        (width.max(1), height.max(1))
+    }
+}
+
+/// Tiled Xbox 360 surfaces are padded to whole pages.
+const XBOX_PAGE_SIZE: usize = 4096;
+
+/// The size of the words the Xbox 360 stores in big-endian order.
+fn xbox_word_size(format: RenderFormat) -> usize {
+    match format {
+        RenderFormat::R8G8B8A8 => 4,
+        RenderFormat::A8 => 1,
+        _ => 2,
+    }
+}
+
+/// The position of the block at `offset` in a tiled Xbox 360 surface that is `width` blocks wide.
+/// Port of `XGAddress2DTiledX` and `XGAddress2DTiledY` from the Xbox 360 SDK.
+fn xbox_tiled_position(offset: usize, width: usize, block_size: usize) -> (usize, usize) {
+    let aligned_width = width.next_multiple_of(32);
+    let log_bpp = block_size.trailing_zeros() as usize;
+    let offset_b = offset << log_bpp;
+    let offset_t = ((offset_b & !4095) >> 3) + ((offset_b & 1792) >> 2) + (offset_b & 63);
+    let offset_m = offset_t >> (7 + log_bpp);
+
+    let macro_x = (offset_m % (aligned_width >> 5)) << 2;
+    let tile_x = (((offset_t >> (5 + log_bpp)) & 2) + (offset_b >> 6)) & 3;
+    let micro_x =
+        ((((offset_t >> 1) & !15) + (offset_t & 15)) & ((block_size << 3) - 1)) >> log_bpp;
+    let x = ((macro_x + tile_x) << 3) + micro_x;
+
+    let macro_y = (offset_m / (aligned_width >> 5)) << 2;
+    let tile_y = ((offset_t >> (6 + log_bpp)) & 1) + ((offset_b & 2048) >> 10);
+    let micro_y = (((offset_t & (((block_size << 6) - 1) & !31)) + ((offset_t & 15) << 1))
+        >> (3 + log_bpp))
+        & !1;
+    let y = ((macro_y + tile_y) << 3) + micro_y + ((offset_t & 16) >> 4);
+
+    (x, y)
+}
+
+/// The index of a block in a Morton ordered surface. The bits of the larger dimension
+/// that don't have a counterpart in the smaller one are appended above the interleaved bits.
+fn morton_index(x: usize, y: usize, width: usize, height: usize) -> usize {
+    let common_bits = width.min(height).trailing_zeros();
+    let mask = (1 << common_bits) - 1;
+    let mut index = 0;
+    for bit in 0..common_bits {
+        index |= ((x >> bit) & 1) << (2 * bit);
+        index |= ((y >> bit) & 1) << (2 * bit + 1);
+    }
+    let remaining = if width > height { x } else { y };
+    index | ((remaining & !mask) >> common_bits << (2 * common_bits))
+}
+
+/// Maps every block of a single surface between the linear and tiled layout.
+/// `copy` receives the tiled and the linear block index.
+fn for_each_block(
+    tiling: Tiling,
+    format: RenderFormat,
+    width: usize,
+    height: usize,
+    tiled_blocks: usize,
+    mut copy: impl FnMut(usize, usize),
+) {
+    let (block_width, block_height) = block_dimensions(format, width, height);
+    match tiling {
+        Tiling::Linear => (0..block_width * block_height).for_each(|i| copy(i, i)),
+        Tiling::Xbox360 => {
+            for offset in 0..tiled_blocks {
+                let (x, y) = xbox_tiled_position(offset, block_width, format.block_size());
+                if x < block_width && y < block_height {
+                    copy(offset, y * block_width + x);
+                }
+            }
+        }
+        Tiling::Morton => {
+            for y in 0..block_height {
+                for x in 0..block_width {
+                    copy(
+                        morton_index(x, y, block_width, block_height),
+                        y * block_width + x,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The size of a single tiled surface.
+fn tiled_surface_size(tiling: Tiling, format: RenderFormat, width: usize, height: usize) -> usize {
+    let (block_width, block_height) = block_dimensions(format, width, height);
+    match tiling {
+        // the tiles of 8 and 16 bit formats span more than 32 rows,
+        // the surface only covers them once it fills whole 4 KiB pages
+        Tiling::Xbox360 => (block_width.next_multiple_of(32)
+            * block_height.next_multiple_of(32)
+            * format.block_size())
+        .next_multiple_of(XBOX_PAGE_SIZE),
+        Tiling::Linear | Tiling::Morton => block_width * block_height * format.block_size(),
+    }
+}
+
+fn check_morton_dimensions(
+    format: RenderFormat,
+    width: usize,
+    height: usize,
+) -> Result<(), String> {
+    let (block_width, block_height) = block_dimensions(format, width, height);
+    if !block_width.is_power_of_two() || !block_height.is_power_of_two() {
+        return Err(format!(
+            "a swizzled texture needs power of two dimensions, found {width}x{height}"
+        ));
+    }
+    Ok(())
+}
+
+fn swap_words(data: &mut [u8], word_size: usize) {
+    if word_size > 1 {
+        data.chunks_exact_mut(word_size).for_each(<[u8]>::reverse);
+    }
+}
+
+/// Converts the tiled data of a single mip to the linear layout.
+/// Mips holding several surfaces, like the faces of a cubemap, are untiled surface by surface.
+pub(crate) fn untile(
+    tiling: Tiling,
+    format: RenderFormat,
+    width: usize,
+    height: usize,
+    data: &[u8],
+) -> Result<Vec<u8>, String> {
+    if tiling == Tiling::Linear {
+        return Ok(data.to_vec());
+    }
+    if tiling == Tiling::Morton {
+        check_morton_dimensions(format, width, height)?;
+    }
+
+    let block_size = format.block_size();
+    let linear_size = tiled_surface_size(Tiling::Linear, format, width, height);
+    let tiled_size = tiled_surface_size(tiling, format, width, height);
+    if !data.len().is_multiple_of(tiled_size) {
+        return Err(format!(
+            "the mip holds {} bytes, which is not a multiple of the {tiled_size} byte tiled surface",
+            data.len()
+        ));
+    }
+
+    let mut linear = vec![0u8; data.len() / tiled_size * linear_size];
+    for (surface, linear_surface) in data
+        .chunks_exact(tiled_size)
+        .zip(linear.chunks_exact_mut(linear_size))
+    {
+        for_each_block(
+            tiling,
+            format,
+            width,
+            height,
+            tiled_size / block_size,
+            |tiled_block, linear_block| {
+                linear_surface[linear_block * block_size..(linear_block + 1) * block_size]
+                    .copy_from_slice(
+                        &surface[tiled_block * block_size..(tiled_block + 1) * block_size],
+                    );
+            },
+        );
+    }
+    if tiling == Tiling::Xbox360 {
+        swap_words(&mut linear, xbox_word_size(format));
+    }
+    Ok(linear)
+}
+
+/// Converts the linear data of a single mip to the tiled layout, the inverse of [`untile`].
+pub(crate) fn tile(
+    tiling: Tiling,
+    format: RenderFormat,
+    width: usize,
+    height: usize,
+    data: &[u8],
+) -> Result<Vec<u8>, String> {
+    if tiling == Tiling::Linear {
+        return Ok(data.to_vec());
+    }
+    if tiling == Tiling::Morton {
+        check_morton_dimensions(format, width, height)?;
+    }
+
+    let block_size = format.block_size();
+    let linear_size = tiled_surface_size(Tiling::Linear, format, width, height);
+    let tiled_size = tiled_surface_size(tiling, format, width, height);
+    if !data.len().is_multiple_of(linear_size) {
+        return Err(format!(
+            "the mip holds {} bytes, which is not a multiple of the {linear_size} byte surface",
+            data.len()
+        ));
+    }
+
+    let mut tiled = vec![0u8; data.len() / linear_size * tiled_size];
+    for (surface, tiled_surface) in data
+        .chunks_exact(linear_size)
+        .zip(tiled.chunks_exact_mut(tiled_size))
+    {
+        for_each_block(
+            tiling,
+            format,
+            width,
+            height,
+            tiled_size / block_size,
+            |tiled_block, linear_block| {
+                tiled_surface[tiled_block * block_size..(tiled_block + 1) * block_size]
+                    .copy_from_slice(
+                        &surface[linear_block * block_size..(linear_block + 1) * block_size],
+                    );
+            },
+        );
+    }
+    if tiling == Tiling::Xbox360 {
+        swap_words(&mut tiled, xbox_word_size(format));
+    }
+    Ok(tiled)
+}
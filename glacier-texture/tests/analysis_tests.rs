@@ -0,0 +1,99 @@
+use glacier_texture::analysis::{AlphaUsage, FormatAnalysis};
+use glacier_texture::enums::{RenderFormat, TextureType};
+use glacier_texture::pack::TextureMapBuilder;
+
+const SIZE: u32 = 16;
+
+fn analyze(texture_type: TextureType, pixel: impl Fn(u32) -> [u8; 4]) -> FormatAnalysis {
+    let pixels = (0..SIZE * SIZE).flat_map(pixel).collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(SIZE, SIZE, &pixels)
+        .unwrap()
+        .with_texture_type(texture_type)
+        .analyze_format()
+        .unwrap()
+}
+
+#[test]
+fn opaque_colour_with_a_constant_blue_channel_is_bc1() {
+    let analysis = analyze(TextureType::Colour, |i| {
+        [i as u8, (i * 3) as u8, 0x80, 0xFF]
+    });
+    assert_eq!(analysis.format, RenderFormat::BC1);
+    assert!(!analysis.two_channel);
+
+    let analysis = analyze(TextureType::Colour, |i| {
+        [i as u8, (i * 3) as u8, 0xFF, 0xFF]
+    });
+    assert_eq!(analysis.format, RenderFormat::BC1);
+    assert!(analysis.two_channel);
+}
+
+#[test]
+fn two_channel_data_with_blue_at_zero_is_bc5() {
+    let analysis = analyze(TextureType::Colour, |i| [i as u8, (i * 3) as u8, 0, 0xFF]);
+    assert!(analysis.two_channel);
+    assert_eq!(analysis.format, RenderFormat::BC5);
+
+    // BC5 has no alpha channel
+    let analysis = analyze(TextureType::Colour, |i| {
+        [i as u8, (i * 3) as u8, 0, i as u8]
+    });
+    assert!(analysis.two_channel);
+    assert_eq!(analysis.format, RenderFormat::BC7);
+}
+
+#[test]
+fn normal_maps_are_bc5() {
+    let analysis = analyze(TextureType::Normal, |i| {
+        [i as u8, (i * 3) as u8, 0xFF, 0xFF]
+    });
+    assert_eq!(analysis.format, RenderFormat::BC5);
+    assert!(analysis.two_channel);
+}
+
+#[test]
+fn alpha_usage_picks_the_alpha_format() {
+    let analysis = analyze(TextureType::Colour, |i| {
+        [i as u8, 0x20, 0x40, if i % 2 == 0 { 0 } else { 0xFF }]
+    });
+    assert_eq!(analysis.alpha, AlphaUsage::OneBit);
+    assert_eq!(analysis.format, RenderFormat::BC3);
+
+    let analysis = analyze(TextureType::Colour, |i| [i as u8, 0x20, 0x40, i as u8]);
+    assert_eq!(analysis.alpha, AlphaUsage::Gradient);
+    assert_eq!(analysis.format, RenderFormat::BC7);
+
+    let analysis = analyze(TextureType::Colour, |i| [0x10, 0x20, 0x40, i as u8]);
+    assert!(analysis.alpha_only);
+    assert_eq!(analysis.format, RenderFormat::A8);
+}
+
+#[test]
+fn grayscale_textures_are_bc4() {
+    for texture_type in [
+        TextureType::Colour,
+        TextureType::Emission,
+        TextureType::Height,
+    ] {
+        let analysis = analyze(texture_type, |i| [i as u8, i as u8, i as u8, 0xFF]);
+        assert!(analysis.grayscale);
+        assert_eq!(analysis.format, RenderFormat::BC4, "{texture_type:?}");
+    }
+
+    // a grayscale mask with alpha still needs a format with an alpha channel
+    let analysis = analyze(TextureType::Colour, |i| {
+        [i as u8, i as u8, i as u8, i as u8]
+    });
+    assert!(analysis.grayscale);
+    assert_eq!(analysis.format, RenderFormat::BC7);
+}
+
+#[test]
+fn single_channel_textures_are_bc4() {
+    let analysis = analyze(TextureType::Height, |i| [i as u8, i as u8, i as u8, 0xFF]);
+    assert!(analysis.grayscale);
+    assert_eq!(analysis.format, RenderFormat::BC4);
+
+    let analysis = analyze(TextureType::Height, |i| [i as u8, 0, 0, 0xFF]);
+    assert_eq!(analysis.format, RenderFormat::BC4);
+}
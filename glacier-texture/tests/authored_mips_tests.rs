@@ -0,0 +1,90 @@
+use glacier_texture::convert;
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::{MipLevels, TextureMapBuilder, TexturePackerError};
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+use std::io::Cursor;
+
+/// A single level DDS filled with one value.
+fn flat_dds(size: u32, value: u8, format: RenderFormat) -> Vec<u8> {
+    let pixels = vec![value; (size * size * 4) as usize];
+    let texture = TextureMapBuilder::from_rgba8(size, size, &pixels)
+        .unwrap()
+        .with_format(format)
+        .with_num_mip_levels(MipLevels::Limit(1))
+        .with_mipblock1(false)
+        .build(WoaVersion::HM3)
+        .unwrap();
+    convert::create_dds(&texture).unwrap()
+}
+
+fn build(builder: TextureMapBuilder) -> TextureMap {
+    builder
+        .with_format(RenderFormat::R8G8B8A8)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+#[test]
+fn authored_levels_are_kept() {
+    let levels = [(16, 0x10), (8, 0x80), (4, 0xF0)]
+        .map(|(size, value)| Cursor::new(flat_dds(size, value, RenderFormat::R8G8B8A8)));
+    let texture = build(TextureMapBuilder::from_dds_levels(levels).unwrap());
+
+    assert_eq!(texture.num_mip_levels(), 5);
+    for (level, value) in [(0, 0x10), (1, 0x80), (2, 0xF0)] {
+        let mip = texture.mipmap(level).unwrap();
+        assert!(mip.data.iter().all(|v| *v == value), "mip {level}");
+    }
+    // the missing levels are generated from the last authored one
+    for level in 3..5 {
+        let mip = texture.mipmap(level).unwrap();
+        assert!(mip.data.iter().all(|v| *v == 0xF0), "mip {level}");
+    }
+}
+
+#[test]
+fn authored_mips_of_a_dds_are_only_kept_when_enabled() {
+    let source = build(
+        TextureMapBuilder::from_dds_levels([
+            Cursor::new(flat_dds(8, 0x00, RenderFormat::R8G8B8A8)),
+            Cursor::new(flat_dds(4, 0xFF, RenderFormat::R8G8B8A8)),
+        ])
+        .unwrap(),
+    );
+    let dds = convert::create_dds(&source).unwrap();
+
+    let kept = build(
+        TextureMapBuilder::from_dds(Cursor::new(&dds))
+            .unwrap()
+            .with_authored_mips(true),
+    );
+    assert!(kept.mipmap(1).unwrap().data.iter().all(|v| *v == 0xFF));
+
+    let generated = build(TextureMapBuilder::from_dds(Cursor::new(&dds)).unwrap());
+    assert!(generated.mipmap(1).unwrap().data.iter().all(|v| *v == 0x00));
+}
+
+#[test]
+fn levels_have_to_halve_in_size() {
+    let result = TextureMapBuilder::from_dds_levels([
+        Cursor::new(flat_dds(16, 0, RenderFormat::R8G8B8A8)),
+        Cursor::new(flat_dds(4, 0, RenderFormat::R8G8B8A8)),
+    ]);
+    assert!(matches!(result, Err(TexturePackerError::PackingError(_))));
+}
+
+#[test]
+fn levels_have_to_share_a_format() {
+    let result = TextureMapBuilder::from_dds_levels([
+        Cursor::new(flat_dds(16, 0, RenderFormat::R8G8B8A8)),
+        Cursor::new(flat_dds(8, 0, RenderFormat::BC1)),
+    ]);
+    assert!(matches!(result, Err(TexturePackerError::PackingError(_))));
+}
+
+#[test]
+fn at_least_one_level_is_needed() {
+    let result = TextureMapBuilder::from_dds_levels(Vec::<Cursor<Vec<u8>>>::new());
+    assert!(matches!(result, Err(TexturePackerError::PackingError(_))));
+}
@@ -0,0 +1,116 @@
+use glacier_texture::convert;
+use glacier_texture::enums::{Channel, RenderFormat};
+use glacier_texture::pack::{ChannelSource, MipLevels, TextureMapBuilder, TexturePackerError};
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+const SIZE: u32 = 8;
+
+fn build(builder: TextureMapBuilder, format: RenderFormat) -> TextureMap {
+    builder
+        .with_format(format)
+        .with_num_mip_levels(MipLevels::Limit(1))
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+fn ramp(offset: u8) -> Vec<u8> {
+    (0..SIZE * SIZE)
+        .map(|i| (i as u8).wrapping_add(offset))
+        .collect()
+}
+
+/// Reads the values of a grayscale PNG.
+fn decode_png(png: &[u8]) -> Vec<u8> {
+    let mut reader = png::Decoder::new(png).read_info().unwrap();
+    let mut values = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut values).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Grayscale);
+    values.truncate(info.buffer_size());
+    values
+}
+
+#[test]
+fn channels_are_packed_in_order() {
+    let builder = TextureMapBuilder::from_channels(
+        SIZE,
+        SIZE,
+        [
+            ChannelSource::Grayscale(ramp(0)),
+            ChannelSource::Constant(0x40),
+            ChannelSource::Grayscale(ramp(100)),
+            ChannelSource::Constant(0xFF),
+        ],
+    )
+    .unwrap();
+    let texture = build(builder, RenderFormat::R8G8B8A8);
+
+    let data = texture.mipmap(0).unwrap().data;
+    for (i, pixel) in data.chunks_exact(4).enumerate() {
+        assert_eq!(pixel, [i as u8, 0x40, (i as u8).wrapping_add(100), 0xFF]);
+    }
+}
+
+#[test]
+fn channel_sizes_have_to_match() {
+    let result = TextureMapBuilder::from_channels(
+        SIZE,
+        SIZE,
+        [
+            ChannelSource::Constant(0),
+            ChannelSource::Grayscale(vec![0; 3]),
+            ChannelSource::Constant(0),
+            ChannelSource::Constant(0),
+        ],
+    );
+    assert!(matches!(result, Err(TexturePackerError::PackingError(_))));
+}
+
+#[test]
+fn every_stored_channel_is_exported() {
+    let builder = TextureMapBuilder::from_channels(
+        SIZE,
+        SIZE,
+        [
+            ChannelSource::Grayscale(ramp(0)),
+            ChannelSource::Grayscale(ramp(50)),
+            ChannelSource::Constant(0x80),
+            ChannelSource::Constant(0xC0),
+        ],
+    )
+    .unwrap();
+    let texture = build(builder, RenderFormat::R8G8B8A8);
+
+    let pngs = convert::create_channel_pngs(&texture, 0).unwrap();
+    let channels = pngs.iter().map(|(channel, _)| *channel).collect::<Vec<_>>();
+    assert_eq!(
+        channels,
+        [Channel::Red, Channel::Green, Channel::Blue, Channel::Alpha]
+    );
+    assert_eq!(decode_png(&pngs[0].1), ramp(0));
+    assert_eq!(decode_png(&pngs[1].1), ramp(50));
+    assert!(decode_png(&pngs[2].1).iter().all(|v| *v == 0x80));
+    assert!(decode_png(&pngs[3].1).iter().all(|v| *v == 0xC0));
+}
+
+#[test]
+fn only_stored_channels_are_exported() {
+    let texture = build(
+        TextureMapBuilder::from_rg8(SIZE, SIZE, &[0x10, 0x20].repeat((SIZE * SIZE) as usize))
+            .unwrap(),
+        RenderFormat::R8G8,
+    );
+    let pngs = convert::create_channel_pngs(&texture, 0).unwrap();
+    assert_eq!(pngs.len(), 2);
+    assert_eq!(pngs[1].0, Channel::Green);
+    assert!(decode_png(&pngs[1].1).iter().all(|v| *v == 0x20));
+
+    let texture = build(
+        TextureMapBuilder::from_r8(SIZE, SIZE, &ramp(0)).unwrap(),
+        RenderFormat::A8,
+    );
+    let pngs = convert::create_channel_pngs(&texture, 0).unwrap();
+    assert_eq!(pngs.len(), 1);
+    assert_eq!(pngs[0].0, Channel::Alpha);
+    assert_eq!(decode_png(&pngs[0].1), ramp(0));
+}
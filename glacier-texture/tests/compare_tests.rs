@@ -0,0 +1,157 @@
+use glacier_texture::compare;
+use glacier_texture::convert;
+use glacier_texture::enums::{Channel, RenderFormat};
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::{MipLevel, TextureMap};
+use glacier_texture::WoaVersion;
+
+fn pixels(size: usize) -> Vec<u8> {
+    (0..size * size)
+        .flat_map(|i| {
+            let (x, y) = ((i % size) as u8, (i / size) as u8);
+            [
+                x.wrapping_mul(8),
+                y.wrapping_mul(8),
+                x.wrapping_add(y),
+                0xFF,
+            ]
+        })
+        .collect()
+}
+
+fn build(size: usize, format: RenderFormat) -> TextureMap {
+    TextureMapBuilder::from_rgba8(size as u32, size as u32, &pixels(size))
+        .unwrap()
+        .with_format(format)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+fn flat_mip(size: usize, value: u8) -> MipLevel {
+    MipLevel {
+        format: RenderFormat::R8G8B8A8,
+        width: size,
+        height: size,
+        data: vec![value; size * size * 4],
+    }
+}
+
+#[test]
+fn texture_is_identical_to_itself() {
+    let texture = build(32, RenderFormat::R8G8B8A8);
+    let comparison = compare::compare_texture_maps(&texture, &texture).unwrap();
+    assert!(comparison.is_identical());
+    assert_eq!(comparison.mips.len(), texture.num_mip_levels());
+    assert!(comparison.unmatched_mips.is_empty());
+    assert_eq!(comparison.min_psnr(), f64::INFINITY);
+    for channel in comparison.mips.iter().flat_map(|mip| &mip.channels) {
+        assert!((channel.ssim - 1.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn compression_loss_is_measured() {
+    let original = build(32, RenderFormat::R8G8B8A8);
+    let compressed = build(32, RenderFormat::BC1);
+    let comparison = compare::compare_texture_maps(&original, &compressed).unwrap();
+
+    assert!(!comparison.is_identical());
+    let format = comparison
+        .header_differences
+        .iter()
+        .find(|difference| difference.field == "format")
+        .unwrap();
+    assert_eq!(format.left, "R8G8B8A8");
+    assert_eq!(format.right, "BC1");
+
+    assert!(comparison.min_psnr().is_finite());
+    let top = &comparison.mips[0];
+    assert_eq!((top.left_level, top.right_level), (0, 0));
+    assert!(top.channels.iter().any(|channel| channel.max_error > 0));
+    // BC1 keeps the smooth gradient of the top level close
+    assert!(top.channels.iter().all(|channel| channel.psnr > 25.0));
+}
+
+#[test]
+fn mips_are_paired_by_size() {
+    let full = build(256, RenderFormat::BC1);
+    let text_only = full.text_only().unwrap();
+    assert!(text_only.num_mip_levels() < full.num_mip_levels());
+
+    let dropped = full.num_mip_levels() - text_only.num_mip_levels();
+
+    let comparison = compare::compare_texture_maps(&full, &text_only).unwrap();
+    assert_eq!(comparison.mips.len(), text_only.num_mip_levels());
+    for mip in &comparison.mips {
+        assert_eq!(mip.left_level, mip.right_level + dropped);
+        assert!(mip.channels.iter().all(|channel| channel.max_error == 0));
+    }
+    assert_eq!(comparison.unmatched_mips.first(), Some(&(256, 256)));
+    assert_eq!(comparison.unmatched_mips.len(), dropped);
+}
+
+#[test]
+fn texture_matches_its_source_image() {
+    let texture = build(16, RenderFormat::R8G8B8A8);
+    let comparison = compare::compare_with_image(&texture, 16, 16, &pixels(16)).unwrap();
+    assert!(comparison.header_differences.is_empty());
+    assert!(comparison.unmatched_mips.is_empty());
+    let top = &comparison.mips[0];
+    assert!(top.channels.iter().all(|channel| channel.max_error == 0));
+    // the smaller levels are compared against a box filtered source
+    assert!(comparison.min_psnr() > 30.0);
+}
+
+#[test]
+fn single_row_textures_have_no_empty_levels() {
+    let row = pixels(64)[..64 * 4].to_vec();
+    let texture = TextureMapBuilder::from_rgba8(64, 1, &row)
+        .unwrap()
+        .with_format(RenderFormat::R8G8B8A8)
+        .build(WoaVersion::HM3)
+        .unwrap();
+    for comparison in [
+        compare::compare_texture_maps(&texture, &texture).unwrap(),
+        compare::compare_with_image(&texture, 64, 1, &row).unwrap(),
+    ] {
+        assert!(!comparison.mips.is_empty());
+        assert!(comparison
+            .unmatched_mips
+            .iter()
+            .all(|(width, height)| *width > 0 && *height > 0));
+        for mip in &comparison.mips {
+            assert!(mip.width > 0 && mip.height > 0);
+            assert!(mip.channels.iter().all(|channel| !channel.ssim.is_nan()));
+        }
+        assert!(!comparison.min_psnr().is_nan());
+    }
+}
+
+#[test]
+fn source_image_has_to_be_valid() {
+    let texture = build(16, RenderFormat::R8G8B8A8);
+    assert!(compare::compare_with_image(&texture, 0, 16, &pixels(16)).is_err());
+    assert!(compare::compare_with_image(&texture, 16, 16, &pixels(8)).is_err());
+}
+
+#[test]
+fn heatmap_is_a_png() {
+    let left = flat_mip(8, 0x00);
+    let mut right = flat_mip(8, 0x00);
+    right.data[0] = 0xFF;
+    let png = compare::difference_heatmap(&left, &right, &[Channel::Red]).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+    let decoded = convert::decode_mipmap(&build(8, RenderFormat::R8G8B8A8), 0).unwrap();
+    assert!(compare::difference_heatmap(&decoded, &left, &[Channel::Red]).is_ok());
+}
+
+#[test]
+fn heatmap_needs_matching_rgba8_levels() {
+    let left = flat_mip(8, 0);
+    assert!(compare::difference_heatmap(&left, &flat_mip(4, 0), &[Channel::Red]).is_err());
+
+    let mut other = flat_mip(8, 0);
+    other.format = RenderFormat::BC1;
+    assert!(compare::difference_heatmap(&left, &other, &[Channel::Red]).is_err());
+}
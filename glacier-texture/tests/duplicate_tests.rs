@@ -0,0 +1,70 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::scan;
+use glacier_texture::texture_map::{TextureMap, TextureMapError};
+use glacier_texture::WoaVersion;
+use std::fs;
+
+fn build(size: u32) -> TextureMap {
+    let pixels = (0..size * size * 4)
+        .map(|i| (i * 7) as u8)
+        .collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(size, size, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::R8G8B8A8)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+/// The TEXT of `texture` as it's read from disk, without its TEXD.
+fn text_only(texture: &TextureMap) -> TextureMap {
+    TextureMap::from_memory(&texture.pack_to_vec().unwrap(), WoaVersion::HM3).unwrap()
+}
+
+#[test]
+fn content_hash_needs_the_texd() {
+    let texture = build(256);
+    assert!(texture.content_hash().is_ok());
+    assert!(matches!(
+        text_only(&texture).content_hash(),
+        Err(TextureMapError::MissingTexd(1))
+    ));
+}
+
+#[test]
+fn content_hash_ignores_an_unneeded_texd() {
+    let texture = build(16);
+    assert_eq!(
+        text_only(&texture).content_hash().unwrap(),
+        texture.content_hash().unwrap()
+    );
+}
+
+#[test]
+fn directory_duplicates_use_the_texd_next_to_the_text() {
+    let directory =
+        std::env::temp_dir().join(format!("glacier-texture-duplicates-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+
+    let texture = build(256);
+    let text = texture.pack_to_vec().unwrap();
+    let texd = texture
+        .mipblock1()
+        .map(|mipblock| mipblock.pack_to_vec(WoaVersion::HM3).unwrap())
+        .unwrap();
+    for name in ["a", "b"] {
+        fs::write(directory.join(format!("{name}.TEXT")), &text).unwrap();
+        fs::write(directory.join(format!("{name}.TEXD")), &texd).unwrap();
+    }
+    // same TEXT, but without its TEXD
+    fs::write(directory.join("c.TEXT"), &text).unwrap();
+
+    let index = scan::find_duplicates_in_directory(&directory, WoaVersion::HM3).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    let duplicates = index.duplicates();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].members, ["a", "b"]);
+    assert_eq!(index.failures().len(), 1);
+    assert_eq!(index.failures()[0].0, "c");
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "image")]
+
+use glacier_texture::enums::{RenderFormat, TextureType};
+use glacier_texture::image::TextureMapEncoder;
+use glacier_texture::pack::TextureMapParameters;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+use image::{ExtendedColorType, ImageEncoder};
+
+fn encode(
+    configure: impl FnOnce(
+        TextureMapEncoder<&mut Vec<u8>, Vec<u8>>,
+    ) -> TextureMapEncoder<&mut Vec<u8>, Vec<u8>>,
+) -> TextureMap {
+    let pixels = (0..16 * 16 * 4).map(|i| i as u8).collect::<Vec<_>>();
+    let mut text = vec![];
+    let encoder = configure(TextureMapEncoder::from_writers(&mut text, None).with_mipblock1(false));
+    encoder
+        .write_image(&pixels, 16, 16, ExtendedColorType::Rgba8)
+        .unwrap();
+    TextureMap::from_memory(&text, WoaVersion::HM3).unwrap()
+}
+
+#[test]
+fn setters_keep_the_source_format() {
+    let texture = encode(|encoder| encoder.with_texture_type(TextureType::Height));
+    assert_eq!(texture.texture_type(), TextureType::Height);
+    assert_eq!(texture.format(), RenderFormat::R8G8B8A8);
+}
+
+#[test]
+fn setters_apply_on_top_of_params() {
+    let params = TextureMapParameters::new(RenderFormat::BC1);
+    let texture = encode(|encoder| {
+        encoder
+            .with_params(params)
+            .with_texture_type(TextureType::Emission)
+    });
+    assert_eq!(texture.texture_type(), TextureType::Emission);
+    assert_eq!(texture.format(), RenderFormat::BC1);
+}
+
+#[test]
+fn params_replace_earlier_setters() {
+    let params = TextureMapParameters::new(RenderFormat::BC1);
+    let texture = encode(|encoder| encoder.with_format(RenderFormat::A8).with_params(params));
+    assert_eq!(texture.format(), RenderFormat::BC1);
+}
@@ -0,0 +1,129 @@
+use glacier_texture::atlas::AtlasData;
+use glacier_texture::enums::{InterpretAs, RenderFormat, TextureType};
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::{TextureMap, TextureMapError};
+use glacier_texture::WoaVersion;
+
+fn build(size: u32, woa_version: WoaVersion) -> TextureMap {
+    let pixels = vec![0x80; (size * size * 4) as usize];
+    TextureMapBuilder::from_rgba8(size, size, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::BC1)
+        .build(woa_version)
+        .unwrap()
+}
+
+/// Packs the texture and reads the TEXT back.
+fn reread(texture: &TextureMap) -> TextureMap {
+    TextureMap::from_memory(&texture.pack_to_vec().unwrap(), texture.version()).unwrap()
+}
+
+fn is_rejected<T: std::fmt::Debug>(result: Result<T, TextureMapError>) -> bool {
+    matches!(result, Err(TextureMapError::InvalidHeaderEdit(_)))
+}
+
+#[test]
+fn metadata_edits_are_written() {
+    let mut texture = build(64, WoaVersion::HM3);
+    texture.set_texture_type(TextureType::Height).unwrap();
+    texture.set_interpret_as(InterpretAs::Height).unwrap();
+    texture.set_default_mip_level(2).unwrap();
+
+    let read = reread(&texture);
+    assert_eq!(read.texture_type(), TextureType::Height);
+    assert_eq!(read.interpret_as(), Some(InterpretAs::Height));
+    assert_eq!(read.default_mip_level(), 2);
+}
+
+#[test]
+fn default_mip_level_has_to_exist() {
+    let mut texture = build(64, WoaVersion::HM3);
+    let levels = texture.num_mip_levels() as u8;
+    assert!(is_rejected(texture.set_default_mip_level(levels)));
+    assert!(texture.set_default_mip_level(levels - 1).is_ok());
+}
+
+#[test]
+fn hm2_rejects_a_default_mip_level_of_zero() {
+    let mut texture = build(64, WoaVersion::HM2);
+    assert!(is_rejected(texture.set_default_mip_level(0)));
+    assert!(texture.set_default_mip_level(1).is_ok());
+}
+
+#[test]
+fn hm2_has_no_interpret_as() {
+    let mut texture = build(64, WoaVersion::HM2);
+    assert!(is_rejected(texture.set_interpret_as(InterpretAs::Colour)));
+    assert_eq!(texture.interpret_as(), None);
+}
+
+#[test]
+fn edits_that_move_mips_between_text_and_texd_are_rejected() {
+    // UNKNOWN512 textures keep every mip in the TEXT, a 256x256 texture normally keeps one in the TEXD
+    let mut texture = build(256, WoaVersion::HM3);
+    assert!(is_rejected(
+        texture.set_texture_type(TextureType::UNKNOWN512)
+    ));
+    assert_eq!(texture.texture_type(), TextureType::Colour);
+
+    // billboards do the same in HM2016
+    let mut texture = build(256, WoaVersion::HM2016);
+    assert!(is_rejected(
+        texture.set_interpret_as(InterpretAs::Billboard)
+    ));
+    assert_ne!(texture.interpret_as(), Some(InterpretAs::Billboard));
+
+    // small textures are fully stored in the TEXT either way
+    let mut texture = build(64, WoaVersion::HM3);
+    assert!(texture.set_texture_type(TextureType::UNKNOWN512).is_ok());
+}
+
+#[test]
+fn flags_have_to_match_the_atlas() {
+    let mut texture = build(64, WoaVersion::HM3);
+    let mut flags = texture.flags();
+    flags.set_atlas(true);
+    assert!(is_rejected(texture.set_flags(flags)));
+
+    let mut flags = texture.flags();
+    flags.set_deferred(!flags.deferred());
+    let bits = flags.bits();
+    texture.set_flags(flags).unwrap();
+    assert_eq!(reread(&texture).flags().bits(), bits);
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn tiling_flags_cant_be_changed() {
+    let mut texture = build(64, WoaVersion::HM3);
+    let original = texture.mipmap(0).unwrap().data;
+    let flags = texture.flags();
+    assert!(is_rejected(texture.set_flags(flags.with_swizzled(true))));
+    assert!(is_rejected(
+        texture.set_flags(flags.with_memory_read_xbox_360(true))
+    ));
+    assert_eq!(texture.flags().bits(), flags.bits());
+    assert_eq!(texture.mipmap(0).unwrap().data, original);
+}
+
+#[test]
+fn atlas_can_be_added_and_removed() {
+    let mut texture = build(64, WoaVersion::HM3);
+    texture.set_atlas(Some(AtlasData::new_grid(2, 2)));
+    assert!(texture.has_atlas());
+    assert!(texture.flags().atlas());
+
+    let read = reread(&texture);
+    assert!(read.has_atlas());
+    let atlas = read.atlas().as_ref().unwrap();
+    assert_eq!((atlas.width, atlas.height), (2, 2));
+
+    // with an atlas present, the atlas flag can't be cleared
+    let mut flags = texture.flags();
+    flags.set_atlas(false);
+    assert!(is_rejected(texture.set_flags(flags)));
+
+    texture.set_atlas(None);
+    assert!(!texture.flags().atlas());
+    assert!(!reread(&texture).has_atlas());
+}
@@ -0,0 +1,50 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::header_info::TextureMapHeaderInfo;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+fn build(woa_version: WoaVersion) -> TextureMap {
+    let pixels = (0..256 * 256 * 4).map(|i| i as u8).collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(256, 256, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::BC1)
+        .with_mipblock1(false)
+        .build(woa_version)
+        .unwrap()
+}
+
+#[test]
+fn built_header_matches_the_packed_header() {
+    for woa_version in [WoaVersion::HM2016, WoaVersion::HM2, WoaVersion::HM3] {
+        let texture = build(woa_version);
+        let data = texture.pack_to_vec().unwrap();
+        let reread = TextureMap::from_memory(&data, woa_version).unwrap();
+
+        let info = texture.header_info().unwrap();
+        assert_eq!(info.woa_version(), woa_version);
+        assert_eq!(info, reread.header_info().unwrap(), "{woa_version:?}");
+    }
+}
+
+#[test]
+fn header_values_are_decoded() {
+    let data = build(WoaVersion::HM3).pack_to_vec().unwrap();
+    let texture = TextureMap::from_memory(&data, WoaVersion::HM3).unwrap();
+    let TextureMapHeaderInfo::V3(info) = texture.header_info().unwrap() else {
+        panic!("expected a HM3 header");
+    };
+
+    assert_eq!(info.data_size as usize, data.len());
+    assert_eq!(info.num_textures, 1);
+    assert_eq!(info.format.decoded, Some(RenderFormat::BC1));
+    assert_eq!(info.format.raw, RenderFormat::BC1 as u16);
+    assert_eq!(info.texture_type.decoded, Some(texture.texture_type()));
+    assert_eq!(info.texture_type.raw, texture.texture_type() as u16);
+    assert!(info.interpret_as.decoded.is_some());
+    assert!(info.dimensions.decoded.is_some());
+    assert_eq!(
+        info.text_scaling_width as usize + info.text_mip_levels as usize,
+        info.num_mip_levels as usize
+    );
+}
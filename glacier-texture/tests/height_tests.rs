@@ -0,0 +1,128 @@
+use glacier_texture::convert;
+use glacier_texture::enums::{InterpretAs, RenderFormat, TextureType};
+use glacier_texture::height::{self, HEIGHT_MAX_KEYWORD, HEIGHT_MIN_KEYWORD};
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+#[test]
+fn height_png_is_16_bit_and_stores_its_range() {
+    let pixels = (0..16 * 16)
+        .flat_map(|i| [(i % 16 * 16) as u8, 0, 0, 0xFF])
+        .collect::<Vec<_>>();
+    let texture = TextureMapBuilder::from_rgba8(16, 16, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::R16G16B16A16)
+        .with_texture_type(TextureType::Height)
+        .with_mipblock1(false)
+        .build(WoaVersion::HM3)
+        .unwrap();
+    assert!(texture.is_height_map());
+
+    let height_png = height::create_height_png(&texture, 0).unwrap();
+    assert_eq!(height_png.min, 0.0);
+    assert!((height_png.max - 240.0 / 255.0).abs() < 1e-3);
+
+    let reader = png::Decoder::new(height_png.png.as_slice())
+        .read_info()
+        .unwrap();
+    let info = reader.info();
+    assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+    assert_eq!(info.color_type, png::ColorType::Grayscale);
+
+    let text = |keyword: &str| {
+        info.uncompressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == keyword)
+            .map(|chunk| chunk.text.parse::<f32>().unwrap())
+    };
+    assert_eq!(text(HEIGHT_MIN_KEYWORD), Some(height_png.min));
+    assert_eq!(text(HEIGHT_MAX_KEYWORD), Some(height_png.max));
+}
+
+#[test]
+fn create_png_exports_height_maps_as_16_bit() {
+    let pixels = [0x40, 0, 0, 0xFF].repeat(16 * 16);
+    let texture = TextureMapBuilder::from_rgba8(16, 16, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::BC4)
+        .with_texture_type(TextureType::Height)
+        .build(WoaVersion::HM3)
+        .unwrap();
+    let png = convert::create_png(&texture).unwrap();
+    assert_eq!(png, height::create_height_png(&texture, 0).unwrap().png);
+
+    let reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    assert_eq!(reader.info().bit_depth, png::BitDepth::Sixteen);
+    assert_eq!(reader.info().color_type, png::ColorType::Grayscale);
+}
+
+/// Builds a normal map from a height field that rises along x.
+fn normals_from_ramp(builder: fn(&[u8]) -> TextureMapBuilder, strength: f32) -> TextureMap {
+    let heights = (0..16 * 16)
+        .map(|i| (i % 16 * 16) as u8)
+        .collect::<Vec<_>>();
+    builder(&heights)
+        .with_normal_from_height(strength)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+/// The red and green value of the pixels away from the edges, where the ramp wraps around.
+fn interior_slopes(texture: &TextureMap) -> Vec<(u8, u8)> {
+    let mip = convert::decode_mipmap(texture, 0).unwrap();
+    mip.data
+        .chunks_exact(4)
+        .enumerate()
+        .filter(|(i, _)| (2..14).contains(&(i % 16)))
+        .map(|(_, pixel)| (pixel[0], pixel[1]))
+        .collect()
+}
+
+#[test]
+fn normal_map_is_derived_from_height() {
+    let from_red = |heights: &[u8]| {
+        let pixels = heights
+            .iter()
+            .flat_map(|h| [*h, 0, 0, 0xFF])
+            .collect::<Vec<_>>();
+        TextureMapBuilder::from_rgba8(16, 16, &pixels).unwrap()
+    };
+    let texture = normals_from_ramp(from_red, 1.0);
+    assert_eq!(texture.format(), RenderFormat::BC5);
+    assert_eq!(texture.texture_type(), TextureType::Normal);
+    assert_eq!(texture.interpret_as(), Some(InterpretAs::Normal));
+
+    // a rising height tilts the normal towards -x, the Sobel filter weighs the 16 / 255 step by 8
+    let slope = 8.0 * 16.0 / 255.0f32;
+    let expected = ((-slope / (1.0 + slope * slope).sqrt() * 0.5 + 0.5) * 255.0) as u8;
+    for (red, green) in interior_slopes(&texture) {
+        assert!(red.abs_diff(expected) <= 4, "{red} {expected}");
+        assert!(green.abs_diff(128) <= 4, "{green}");
+    }
+
+    // a stronger setting gives steeper normals
+    let steep = normals_from_ramp(from_red, 4.0);
+    assert!(interior_slopes(&steep)[0].0 < expected - 20);
+    // without strength the normals point straight up
+    let flat = normals_from_ramp(from_red, 0.0);
+    for (red, green) in interior_slopes(&flat) {
+        assert!(red.abs_diff(128) <= 4 && green.abs_diff(128) <= 4);
+    }
+}
+
+#[test]
+fn normal_map_reads_the_height_of_a8_from_alpha() {
+    let from_a8 = |heights: &[u8]| TextureMapBuilder::from_r8(16, 16, heights).unwrap();
+    let from_rgba = |heights: &[u8]| {
+        let pixels = heights
+            .iter()
+            .flat_map(|h| [*h, 0, 0, 0xFF])
+            .collect::<Vec<_>>();
+        TextureMapBuilder::from_rgba8(16, 16, &pixels).unwrap()
+    };
+    assert_eq!(
+        interior_slopes(&normals_from_ramp(from_a8, 1.0)),
+        interior_slopes(&normals_from_ramp(from_rgba, 1.0))
+    );
+}
@@ -0,0 +1,82 @@
+use glacier_texture::convert;
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+use std::io::Cursor;
+
+const FORMATS: [(RenderFormat, u32); 10] = [
+    (RenderFormat::R16G16B16A16, 97),
+    (RenderFormat::R8G8B8A8, 37),
+    (RenderFormat::R8G8, 16),
+    (RenderFormat::A8, 1000470001),
+    (RenderFormat::BC1, 133),
+    (RenderFormat::BC2, 135),
+    (RenderFormat::BC3, 137),
+    (RenderFormat::BC4, 139),
+    (RenderFormat::BC5, 141),
+    (RenderFormat::BC7, 145),
+];
+
+fn build(format: RenderFormat) -> TextureMap {
+    let pixels = (0..32 * 32)
+        .flat_map(|i: u32| [(i % 32 * 8) as u8, (i / 32 * 16) as u8, 0x60, (i * 3) as u8])
+        .collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(32, 32, &pixels)
+        .unwrap()
+        .with_format(format)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn assert_same_mips(a: &TextureMap, b: &TextureMap) {
+    assert_eq!(a.num_mip_levels(), b.num_mip_levels());
+    for level in 0..a.num_mip_levels() {
+        assert_eq!(a.mipmap(level).unwrap().data, b.mipmap(level).unwrap().data);
+    }
+}
+
+#[test]
+fn ktx2_round_trip() {
+    for (format, vk_format) in FORMATS {
+        let texture = build(format);
+        let ktx2 = convert::create_ktx2(&texture).unwrap();
+
+        assert_eq!(read_u32(&ktx2, 12), vk_format, "{format:?}");
+        assert_eq!((read_u32(&ktx2, 20), read_u32(&ktx2, 24)), (32, 32));
+        assert_eq!(read_u32(&ktx2, 40), texture.num_mip_levels() as u32);
+
+        let builder = TextureMapBuilder::from_ktx2(Cursor::new(ktx2)).unwrap();
+        assert_eq!(builder.params().format(), format);
+        let imported = builder.build(WoaVersion::HM3).unwrap();
+        assert_eq!(imported.format(), format);
+        assert_same_mips(&texture, &imported);
+    }
+}
+
+#[test]
+fn ktx2_r8_is_read_as_luminance() {
+    let texture = build(RenderFormat::A8);
+    let mut ktx2 = convert::create_ktx2(&texture).unwrap();
+    // VK_FORMAT_R8_UNORM shares the data layout of A8
+    ktx2[12..16].copy_from_slice(&9u32.to_le_bytes());
+
+    let imported = TextureMapBuilder::from_ktx2(Cursor::new(ktx2))
+        .unwrap()
+        .build(WoaVersion::HM3)
+        .unwrap();
+    assert_eq!(imported.format(), RenderFormat::A8);
+    assert_same_mips(&texture, &imported);
+
+    let imported =
+        TextureMapBuilder::from_ktx2(Cursor::new(convert::create_ktx2(&texture).unwrap()))
+            .unwrap()
+            .with_format(RenderFormat::BC4)
+            .build(WoaVersion::HM3)
+            .unwrap();
+    assert_eq!(imported.format(), RenderFormat::BC4);
+}
@@ -0,0 +1,75 @@
+use glacier_texture::atlas::AtlasData;
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+const VERSIONS: [WoaVersion; 3] = [WoaVersion::HM2016, WoaVersion::HM2, WoaVersion::HM3];
+
+fn build(woa_version: WoaVersion, atlas: bool, seed: u8) -> TextureMap {
+    let pixels = (0..256 * 256)
+        .flat_map(|i: u32| [(i % 256) as u8, (i / 256) as u8, seed, 0xFF])
+        .collect::<Vec<_>>();
+    let mut builder = TextureMapBuilder::from_rgba8(256, 256, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::BC1);
+    if atlas {
+        builder = builder.with_atlas(AtlasData::new_grid(2, 2));
+    }
+    builder.build(woa_version).unwrap()
+}
+
+/// The offset of the data size field in the header.
+fn data_size_offset(woa_version: WoaVersion) -> usize {
+    match woa_version {
+        WoaVersion::HM2016 => 8,
+        WoaVersion::HM2 | WoaVersion::HM3 => 4,
+    }
+}
+
+#[test]
+fn lossless_round_trip() {
+    for woa_version in VERSIONS {
+        for atlas in [false, true] {
+            let built = build(woa_version, atlas, 0x40);
+            let mut text = built.pack_to_vec().unwrap();
+            // a value that is normally recalculated, it should survive a lossless round trip
+            text[data_size_offset(woa_version)] ^= 0x01;
+
+            let texture = TextureMap::from_memory(&text, woa_version).unwrap();
+            assert_eq!(texture.has_atlas(), atlas);
+            assert_eq!(texture.pack_to_vec_lossless().unwrap(), text);
+            assert_eq!(texture.verify_lossless(&text).unwrap(), None);
+            assert_ne!(texture.pack_to_vec().unwrap(), text);
+
+            let repacked =
+                TextureMap::from_memory(&texture.pack_to_vec_lossless().unwrap(), woa_version)
+                    .unwrap();
+            assert_eq!(repacked.pack_to_vec_lossless().unwrap(), text);
+        }
+    }
+}
+
+#[test]
+fn lossless_round_trip_with_texd() {
+    for woa_version in VERSIONS {
+        for atlas in [false, true] {
+            let built = build(woa_version, atlas, 0x40);
+            let text = built.pack_to_vec().unwrap();
+
+            let mut texture = TextureMap::from_memory(&text, woa_version).unwrap();
+            texture.set_mipblock1(built.mipblock1().unwrap());
+            assert_eq!(texture.num_mip_levels(), built.num_mip_levels());
+            assert_eq!(texture.verify_lossless(&text).unwrap(), None);
+
+            // the preserved TEXT belongs to the first mipblock
+            let other = build(woa_version, atlas, 0xC0);
+            texture.set_mipblock1(other.mipblock1().unwrap());
+            assert_eq!(
+                texture.pack_to_vec_lossless().unwrap(),
+                texture.pack_to_vec().unwrap()
+            );
+            assert_eq!(texture.pack_to_vec().unwrap(), other.pack_to_vec().unwrap());
+        }
+    }
+}
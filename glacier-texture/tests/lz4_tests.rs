@@ -0,0 +1,84 @@
+use glacier_texture::convert;
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::header_info::TextureMapHeaderInfo;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+use lz4::block::CompressionMode;
+
+fn build(format: RenderFormat) -> TextureMap {
+    let pixels = (0..256 * 256 * 4)
+        .map(|i: u32| ((i / 4 % 256) ^ (i / 1024)) as u8)
+        .collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(256, 256, &pixels)
+        .unwrap()
+        .with_format(format)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+/// The mip levels compressed one after another, the way the serial path lays them out.
+fn compress_serially(mips: &[Vec<u8>]) -> (Vec<u8>, Vec<u32>) {
+    let mut data = vec![];
+    let mut sizes = vec![];
+    for mip in mips {
+        data.extend(
+            lz4::block::compress(mip, Some(CompressionMode::HIGHCOMPRESSION(12)), false).unwrap(),
+        );
+        sizes.push(data.len() as u32);
+    }
+    (data, sizes)
+}
+
+#[test]
+fn compressed_mips_match_the_serial_layout() {
+    for format in [RenderFormat::R8G8B8A8, RenderFormat::BC1] {
+        let texture = build(format);
+        let mips = texture
+            .mipmaps()
+            .map(|mip| mip.unwrap().data)
+            .collect::<Vec<_>>();
+        let (expected, sizes) = compress_serially(&mips);
+
+        assert_eq!(texture.mipblock1().unwrap().data, expected, "{format:?}");
+        let TextureMapHeaderInfo::V3(header) = texture.header_info().unwrap() else {
+            panic!("not a HM3 texture");
+        };
+        assert_eq!(&header.compressed_mip_sizes[..sizes.len()], sizes);
+    }
+}
+
+#[test]
+fn packing_is_deterministic() {
+    let first = build(RenderFormat::BC1);
+    let second = build(RenderFormat::BC1);
+    assert_eq!(first.pack_to_vec().unwrap(), second.pack_to_vec().unwrap());
+    assert_eq!(
+        first.mipblock1().unwrap().data,
+        second.mipblock1().unwrap().data
+    );
+}
+
+#[test]
+fn decompressed_mips_match_single_levels() {
+    let texture = build(RenderFormat::R8G8B8A8);
+    let all = texture.mipmaps().collect::<Vec<_>>();
+    assert_eq!(all.len(), texture.num_mip_levels());
+    for (level, mip) in all.into_iter().enumerate() {
+        let mip = mip.unwrap();
+        assert_eq!(mip.data, texture.mipmap(level).unwrap().data, "mip {level}");
+        assert_eq!(mip.width, 256 >> level);
+    }
+
+    // the decompressed levels decode to the same image as the uncompressed DDS export
+    let dds = convert::create_dds(&texture).unwrap();
+    let reread = TextureMapBuilder::from_dds(std::io::Cursor::new(dds))
+        .unwrap()
+        .with_authored_mips(true)
+        .build(WoaVersion::HM3)
+        .unwrap();
+    assert_eq!(
+        reread.mipblock1().unwrap().data,
+        texture.mipblock1().unwrap().data
+    );
+}
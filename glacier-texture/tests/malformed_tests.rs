@@ -0,0 +1,105 @@
+use glacier_texture::compare;
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::{MipLevels, TextureMapBuilder, TexturePackerError};
+use glacier_texture::texture_map::{TextureMap, TextureMapError};
+use glacier_texture::WoaVersion;
+
+/// A 4x4 BC1 header without any mip levels, the header still assigns one mip to the TEXD.
+fn empty_bc1_header(woa_version: WoaVersion) -> Vec<u8> {
+    let mut data = vec![];
+    data.extend(1u16.to_le_bytes()); // num_textures
+    data.extend(0u16.to_le_bytes()); // texture type
+    data.extend(0u32.to_le_bytes()); // data size
+    data.extend(0u32.to_le_bytes()); // flags
+    data.extend(4u16.to_le_bytes());
+    data.extend(4u16.to_le_bytes());
+    data.extend(0x49u16.to_le_bytes()); // BC1
+    data.extend([0, 0]); // num_mip_levels, default_mip_level
+    match woa_version {
+        WoaVersion::HM2 => data.extend(0x4000u32.to_le_bytes()), // texd identifier
+        _ => data.extend([0, 0, 0, 0]), // interpret_as, dimensions, mips_interpolation_deprecated
+    }
+    data.extend([0; 2 * 14 * 4]); // mip_sizes, compressed_mip_sizes
+    data.extend([0; 8]); // atlas size and offset
+    if woa_version == WoaVersion::HM3 {
+        data.extend([0xFF, 0, 0, 0]); // text scaling data
+        data.extend([0; 4]); // padding
+    }
+    data
+}
+
+#[test]
+fn reading_a_texture_without_mips_fails() {
+    for woa_version in [WoaVersion::HM2, WoaVersion::HM3] {
+        assert!(matches!(
+            TextureMap::from_memory(&empty_bc1_header(woa_version), woa_version),
+            Err(TextureMapError::InvalidTextScale(0, 1))
+        ));
+    }
+}
+
+#[test]
+fn comparing_with_an_empty_image_fails() {
+    let texture = TextureMapBuilder::from_rgba8(4, 4, &[0x80; 4 * 4 * 4])
+        .unwrap()
+        .with_format(RenderFormat::R8G8B8A8)
+        .build(WoaVersion::HM3)
+        .unwrap();
+    assert!(compare::compare_with_image(&texture, 4, 0, &[]).is_err());
+    assert!(compare::compare_with_image(&texture, 0, 4, &[]).is_err());
+}
+
+#[test]
+fn building_with_fewer_mips_than_the_text_scale_fails() {
+    // the area of a 1024x1024 texture puts its TEXT three mips down, but the chain only has two
+    let pixels = vec![0x80; 1024 * 1024 * 4];
+    for woa_version in [WoaVersion::HM2016, WoaVersion::HM2, WoaVersion::HM3] {
+        let result = TextureMapBuilder::from_rgba8(1024, 1024, &pixels)
+            .unwrap()
+            .with_format(RenderFormat::R8G8B8A8)
+            .with_num_mip_levels(MipLevels::Limit(2))
+            .build(woa_version);
+        assert!(
+            matches!(
+                result,
+                Err(TexturePackerError::TextureMapError(
+                    TextureMapError::InvalidTextScale(2, 3)
+                ))
+            ),
+            "{woa_version:?}"
+        );
+    }
+}
+
+#[test]
+fn reading_fewer_mips_than_the_text_scale_fails() {
+    let pixels = vec![0x80; 1024 * 1024 * 4];
+    for woa_version in [WoaVersion::HM2016, WoaVersion::HM2, WoaVersion::HM3] {
+        let mut data = TextureMapBuilder::from_rgba8(1024, 1024, &pixels)
+            .unwrap()
+            .with_format(RenderFormat::R8G8B8A8)
+            .with_num_mip_levels(MipLevels::Limit(4))
+            .build(woa_version)
+            .unwrap()
+            .pack_to_vec()
+            .unwrap();
+        let num_mip_levels_offset = match woa_version {
+            WoaVersion::HM2016 => 22,
+            _ => 18,
+        };
+        assert_eq!(data[num_mip_levels_offset], 4);
+        data[num_mip_levels_offset] = 2;
+        if woa_version == WoaVersion::HM3 {
+            // keep the stored scaling consistent with the mip count, so only the split formula rejects it
+            data[145..148].copy_from_slice(&[2, 2, 0]);
+        }
+
+        assert!(
+            matches!(
+                TextureMap::from_memory(&data, woa_version),
+                Err(TextureMapError::InvalidTextScale(2, 3))
+            ),
+            "{woa_version:?}"
+        );
+    }
+}
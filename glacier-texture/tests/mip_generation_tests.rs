@@ -0,0 +1,221 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::mipgen::MipGenerationOptions;
+use glacier_texture::pack::{MipFilter, TextureMapBuilder};
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+fn build(
+    builder: TextureMapBuilder,
+    format: RenderFormat,
+    filter: MipFilter,
+    options: MipGenerationOptions,
+) -> TextureMap {
+    builder
+        .with_format(format)
+        .with_mip_filter(filter)
+        .with_mip_generation(options)
+        .with_mipblock1(false)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+fn build_rgba8(
+    size: u32,
+    pixel: impl Fn(u32, u32) -> [u8; 4],
+    filter: MipFilter,
+    options: MipGenerationOptions,
+) -> TextureMap {
+    let pixels = (0..size * size)
+        .flat_map(|i| pixel(i % size, i / size))
+        .collect::<Vec<_>>();
+    let builder = TextureMapBuilder::from_rgba8(size, size, &pixels).unwrap();
+    build(builder, RenderFormat::R8G8B8A8, filter, options)
+}
+
+fn noise(x: u32, y: u32) -> u8 {
+    let mut state = (x * 7919 + y * 104_729) ^ 0x9E37_79B9;
+    state ^= state << 13;
+    state ^= state >> 17;
+    state ^= state << 5;
+    state as u8
+}
+
+/// The fraction of pixels whose alpha is above `cutoff`.
+fn coverage(texture: &TextureMap, level: usize, cutoff: u8) -> f32 {
+    let data = texture.mipmap(level).unwrap().data;
+    let covered = data.chunks_exact(4).filter(|p| p[3] > cutoff).count();
+    covered as f32 / (data.len() / 4) as f32
+}
+
+#[test]
+fn alpha_coverage_is_preserved() {
+    let pixel = |x, y| [0x80, 0x80, 0x80, noise(x, y)];
+    let cutoff = 0.7;
+    let preserved = build_rgba8(
+        32,
+        pixel,
+        MipFilter::Box,
+        MipGenerationOptions {
+            alpha_coverage_cutoff: Some(cutoff),
+            ..Default::default()
+        },
+    );
+    // premultiplying fully grey pixels changes nothing, it only switches to the float path
+    let plain = build_rgba8(
+        32,
+        pixel,
+        MipFilter::Box,
+        MipGenerationOptions {
+            premultiplied_alpha: true,
+            ..Default::default()
+        },
+    );
+
+    let cutoff = (cutoff * 255.0) as u8;
+    let target = coverage(&preserved, 0, cutoff);
+    for level in 1..4 {
+        assert!(
+            (coverage(&preserved, level, cutoff) - target).abs() < 0.1,
+            "mip {level}"
+        );
+    }
+    // averaging pulls the alpha towards the middle, so the coverage drops without preservation
+    assert!(coverage(&plain, 2, cutoff) < target - 0.15);
+}
+
+#[test]
+fn linear_light_averages_the_light() {
+    let checker = |x: u32, y: u32| {
+        let value = if (x + y).is_multiple_of(2) { 0 } else { 0xFF };
+        [value, value, value, 0xFF]
+    };
+    let linear = build_rgba8(
+        2,
+        checker,
+        MipFilter::Box,
+        MipGenerationOptions {
+            linear_light: true,
+            ..Default::default()
+        },
+    );
+    let gamma = build_rgba8(2, checker, MipFilter::Box, MipGenerationOptions::default());
+
+    // half of the light is 0.5 linear, which is 0.735 sRGB
+    let mip = linear.mipmap(1).unwrap().data;
+    assert!(mip[0].abs_diff(188) <= 1, "{}", mip[0]);
+    let mip = gamma.mipmap(1).unwrap().data;
+    assert!(mip[0].abs_diff(128) <= 1, "{}", mip[0]);
+}
+
+#[test]
+fn premultiplied_alpha_ignores_transparent_colour() {
+    let pixel = |x: u32, y: u32| {
+        if x == 0 && y == 0 {
+            [0xFF, 0, 0, 0xFF]
+        } else {
+            [0, 0xFF, 0, 0]
+        }
+    };
+    let texture = build_rgba8(
+        2,
+        pixel,
+        MipFilter::Box,
+        MipGenerationOptions {
+            premultiplied_alpha: true,
+            ..Default::default()
+        },
+    );
+    let mip = texture.mipmap(1).unwrap().data;
+    assert_eq!(&mip[..3], [0xFF, 0, 0]);
+    assert!(mip[3].abs_diff(64) <= 1, "{}", mip[3]);
+}
+
+#[test]
+fn normals_are_renormalized() {
+    // (0.6, 0, 0.8) and (-0.6, 0, 0.8) average to (0, 0, 0.8)
+    let pixel = |x: u32, _| {
+        let r = if x.is_multiple_of(2) { 204 } else { 51 };
+        [r, 128, 230, 0xFF]
+    };
+    let options = MipGenerationOptions {
+        renormalize_normals: true,
+        ..Default::default()
+    };
+    let texture = build_rgba8(4, pixel, MipFilter::Box, options);
+    let mip = texture.mipmap(1).unwrap().data;
+    for pixel in mip.chunks_exact(4) {
+        assert!(pixel[0].abs_diff(128) <= 1, "{pixel:?}");
+        assert_eq!(pixel[2], 0xFF, "{pixel:?}");
+    }
+}
+
+#[test]
+fn two_channel_normals_keep_their_slope() {
+    let pixels = [204u8, 128].repeat(16 * 16);
+    let builder = TextureMapBuilder::from_rg8(16, 16, &pixels).unwrap();
+    let options = MipGenerationOptions {
+        renormalize_normals: true,
+        ..Default::default()
+    };
+    let texture = build(builder, RenderFormat::R8G8, MipFilter::Box, options);
+    for level in 0..texture.num_mip_levels() {
+        let mip = texture.mipmap(level).unwrap().data;
+        for pixel in mip.chunks_exact(2) {
+            assert!(pixel[0].abs_diff(204) <= 1, "mip {level}: {pixel:?}");
+            assert!(pixel[1].abs_diff(128) <= 1, "mip {level}: {pixel:?}");
+        }
+    }
+}
+
+#[test]
+fn the_mip_filter_is_used() {
+    // opaque pixels are not changed by premultiplying, this only enables the float path
+    let options = MipGenerationOptions {
+        premultiplied_alpha: true,
+        ..Default::default()
+    };
+    let stripes = |x: u32, _| {
+        let value = [0, 0x80, 0xFF, 0xFF][x as usize % 4];
+        [value, value, value, 0xFF]
+    };
+    let mip = |filter| {
+        build_rgba8(8, stripes, filter, options)
+            .mipmap(1)
+            .unwrap()
+            .data
+    };
+
+    let nearest = mip(MipFilter::Nearest);
+    assert_eq!(&nearest[..8], [0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+    let boxed = mip(MipFilter::Box);
+    assert_eq!(boxed[0], 0x40);
+    assert_eq!(boxed[4], 0xFF);
+
+    // the tent of the linear filter reaches into the neighbouring stripes
+    let linear = mip(MipFilter::Linear);
+    assert_ne!(linear[4], boxed[4]);
+    let cubic = mip(MipFilter::Cubic);
+    assert_ne!(cubic[4], linear[4]);
+}
+
+#[test]
+fn hdr_sources_keep_their_range() {
+    // opaque pixels are not changed by premultiplying, this only enables the float path
+    let options = MipGenerationOptions {
+        premultiplied_alpha: true,
+        ..Default::default()
+    };
+    let pixels = [4.0, 2.0, 1.5, 1.0].repeat(8 * 8);
+    let builder = TextureMapBuilder::from_rgba32f(8, 8, &pixels).unwrap();
+    let texture = build(builder, RenderFormat::R16G16B16A16, MipFilter::Box, options);
+
+    // 4.0, 2.0, 1.5 and 1.0 as half precision floats
+    let expected = [0x4400u16, 0x4000, 0x3E00, 0x3C00];
+    let mip = texture.mipmap(1).unwrap();
+    assert_eq!((mip.width, mip.height), (4, 4));
+    for pixel in mip.data.chunks_exact(8) {
+        let pixel = [0, 2, 4, 6].map(|i| u16::from_le_bytes([pixel[i], pixel[i + 1]]));
+        assert_eq!(pixel, expected);
+    }
+}
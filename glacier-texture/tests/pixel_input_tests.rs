@@ -0,0 +1,81 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::{MipLevels, PixelLayout, TextureMapBuilder, TexturePackerError};
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+const SIZE: u32 = 8;
+
+fn build(builder: TextureMapBuilder) -> TextureMap {
+    builder
+        .with_num_mip_levels(MipLevels::Limit(1))
+        .with_mipblock1(false)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+fn bytes(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 13) as u8).collect()
+}
+
+#[test]
+fn rgba8_pixels_are_stored_as_given() {
+    let pixels = bytes((SIZE * SIZE * 4) as usize);
+    let texture = build(TextureMapBuilder::from_rgba8(SIZE, SIZE, &pixels).unwrap());
+    assert_eq!(texture.format(), RenderFormat::R8G8B8A8);
+    assert_eq!(texture.mipmap(0).unwrap().data, pixels);
+}
+
+#[test]
+fn rg8_pixels_are_stored_as_given() {
+    let pixels = bytes((SIZE * SIZE * 2) as usize);
+    let texture = build(TextureMapBuilder::from_rg8(SIZE, SIZE, &pixels).unwrap());
+    assert_eq!(texture.format(), RenderFormat::R8G8);
+    assert_eq!(texture.mipmap(0).unwrap().data, pixels);
+}
+
+#[test]
+fn r8_pixels_default_to_a8() {
+    let pixels = bytes((SIZE * SIZE) as usize);
+    let texture = build(TextureMapBuilder::from_r8(SIZE, SIZE, &pixels).unwrap());
+    assert_eq!(texture.format(), RenderFormat::A8);
+    assert_eq!(texture.mipmap(0).unwrap().data, pixels);
+}
+
+#[test]
+fn float_pixels_are_stored_as_half_floats() {
+    // 1.0, 0.5, 0.25 and 0.0 as half precision floats
+    let half = [0x3C00u16, 0x3800, 0x3400, 0x0000];
+    let pixels = half.repeat((SIZE * SIZE) as usize);
+    let expected = pixels
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect::<Vec<_>>();
+
+    let texture = build(TextureMapBuilder::from_rgba16f(SIZE, SIZE, &pixels).unwrap());
+    assert_eq!(texture.format(), RenderFormat::R16G16B16A16);
+    assert_eq!(texture.mipmap(0).unwrap().data, expected);
+
+    let pixels = [1.0f32, 0.5, 0.25, 0.0].repeat((SIZE * SIZE) as usize);
+    let texture = build(TextureMapBuilder::from_rgba32f(SIZE, SIZE, &pixels).unwrap());
+    assert_eq!(texture.format(), RenderFormat::R16G16B16A16);
+    assert_eq!(texture.mipmap(0).unwrap().data, expected);
+}
+
+#[test]
+fn buffer_size_has_to_match_the_layout() {
+    for layout in [
+        PixelLayout::Rgba8,
+        PixelLayout::Rg8,
+        PixelLayout::R8,
+        PixelLayout::Rgba16F,
+        PixelLayout::Rgba32F,
+    ] {
+        let size = (SIZE * SIZE) as usize * layout.bytes_per_pixel();
+        assert!(TextureMapBuilder::from_pixels(SIZE, SIZE, layout, &bytes(size)).is_ok());
+        assert!(matches!(
+            TextureMapBuilder::from_pixels(SIZE, SIZE, layout, &bytes(size - 1)),
+            Err(TexturePackerError::PackingError(_))
+        ));
+    }
+    assert!(TextureMapBuilder::from_rgba32f(SIZE, SIZE, &[0.0; 3]).is_err());
+}
@@ -0,0 +1,54 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::mipblock::MipblockData;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+fn build(woa_version: WoaVersion) -> TextureMap {
+    let pixels = (0..256 * 256 * 4).map(|i| i as u8).collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(256, 256, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::R8G8B8A8)
+        .build(woa_version)
+        .unwrap()
+}
+
+fn reread(texture: &TextureMap, woa_version: WoaVersion) -> TextureMap {
+    let mut reread = TextureMap::from_memory(&texture.pack_to_vec().unwrap(), woa_version).unwrap();
+    if let Some(mipblock) = texture.mipblock1() {
+        let texd = mipblock.pack_to_vec(woa_version).unwrap();
+        reread.set_mipblock1(MipblockData::from_memory(&texd, woa_version).unwrap());
+    }
+    reread
+}
+
+#[test]
+fn porting_keeps_the_mip_data() {
+    let versions = [WoaVersion::HM2016, WoaVersion::HM2, WoaVersion::HM3];
+    for from in versions {
+        let texture = build(from);
+        for to in versions {
+            let ported = reread(&texture.ported(to).unwrap(), to);
+            assert_eq!(ported.format(), texture.format());
+            assert_eq!(ported.num_mip_levels(), texture.num_mip_levels());
+            assert_eq!(
+                (ported.width(), ported.height()),
+                (texture.width(), texture.height())
+            );
+            for level in 0..texture.num_mip_levels() {
+                assert_eq!(
+                    ported.mipmap(level).unwrap().data,
+                    texture.mipmap(level).unwrap().data,
+                    "{from:?} -> {to:?}, mip {level}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn porting_without_the_texd_fails() {
+    let texture = build(WoaVersion::HM3);
+    let text = TextureMap::from_memory(&texture.pack_to_vec().unwrap(), WoaVersion::HM3).unwrap();
+    assert!(text.ported(WoaVersion::HM2016).is_err());
+}
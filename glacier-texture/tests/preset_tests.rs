@@ -0,0 +1,72 @@
+use glacier_texture::enums::{InterpretAs, RenderFormat, TextureType};
+use glacier_texture::pack::{MipLevels, TextureMapBuilder, TextureMapParameters, TexturePreset};
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+fn build(preset: TexturePreset, woa_version: WoaVersion) -> TextureMap {
+    let pixels = (0..64 * 64 * 4).map(|i| i as u8).collect::<Vec<_>>();
+    let texture = TextureMapBuilder::from_rgba8(64, 64, &pixels)
+        .unwrap()
+        .with_params(TextureMapParameters::from_preset(preset, woa_version))
+        .with_mipblock1(false)
+        .build(woa_version)
+        .unwrap();
+    TextureMap::from_memory(&texture.pack_to_vec().unwrap(), woa_version).unwrap()
+}
+
+#[test]
+fn normal_preset_sets_normal_metadata() {
+    let params = TextureMapParameters::from_preset(TexturePreset::Normal, WoaVersion::HM3);
+    assert_eq!(params.texture_type(), TextureType::Normal);
+    assert_eq!(params.interpret_as(), InterpretAs::Normal);
+    assert_eq!(params.format(), RenderFormat::BC5);
+    assert!(params.flags().unknown1());
+    assert!(params.flags().unknown3());
+
+    let texture = build(TexturePreset::Normal, WoaVersion::HM3);
+    assert_eq!(texture.texture_type(), TextureType::Normal);
+    assert_eq!(texture.interpret_as(), Some(InterpretAs::Normal));
+    assert_eq!(texture.format(), RenderFormat::BC5);
+}
+
+#[test]
+fn colour_format_depends_on_the_game() {
+    let hm3 = TextureMapParameters::from_preset(TexturePreset::Diffuse, WoaVersion::HM3);
+    assert_eq!(hm3.format(), RenderFormat::BC7);
+    assert_eq!(hm3.interpret_as(), InterpretAs::Colour);
+
+    let hm2 = TextureMapParameters::from_preset(TexturePreset::Diffuse, WoaVersion::HM2);
+    assert_eq!(hm2.format(), RenderFormat::BC1);
+    // HM2 crashes on textures with a default mip level of 0
+    assert_eq!(hm2.default_mip_level(), 1);
+
+    let texture = build(TexturePreset::Diffuse, WoaVersion::HM2);
+    assert_eq!(texture.default_mip_level(), 1);
+    assert_eq!(texture.format(), RenderFormat::BC1);
+}
+
+#[test]
+fn ui_preset_has_a_single_uncompressed_mip() {
+    let params = TextureMapParameters::from_preset(TexturePreset::UI, WoaVersion::HM3);
+    assert!(matches!(params.num_mip_levels(), MipLevels::Limit(1)));
+    assert!(!params.flags().unknown1());
+
+    let texture = build(TexturePreset::UI, WoaVersion::HM3);
+    assert_eq!(texture.num_mip_levels(), 1);
+    assert_eq!(texture.format(), RenderFormat::R8G8B8A8);
+}
+
+#[test]
+fn height_preset_is_single_channel() {
+    let texture = build(TexturePreset::Height, WoaVersion::HM2016);
+    assert_eq!(texture.texture_type(), TextureType::Height);
+    assert_eq!(texture.interpret_as(), Some(InterpretAs::Height));
+    assert_eq!(texture.format(), RenderFormat::BC4);
+}
+
+#[test]
+fn presets_parse_from_their_names() {
+    assert_eq!("Emissive".parse(), Ok(TexturePreset::Emission));
+    assert_eq!("compound-normal".parse(), Ok(TexturePreset::CompoundNormal));
+    assert!("roughness".parse::<TexturePreset>().is_err());
+}
@@ -0,0 +1,91 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::mipgen::MipGenerationOptions;
+use glacier_texture::pack::{TextureMapBuilder, TexturePackerError};
+use glacier_texture::progress::{CancellationToken, Progress, Stage};
+use glacier_texture::WoaVersion;
+use std::sync::{Arc, Mutex};
+
+fn builder() -> TextureMapBuilder {
+    let pixels = (0..64 * 64 * 4).map(|i| i as u8).collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(64, 64, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::BC1)
+}
+
+fn record(builder: TextureMapBuilder) -> (Result<(), TexturePackerError>, Vec<Progress>) {
+    let events = Arc::new(Mutex::new(vec![]));
+    let recorded = events.clone();
+    let result = builder
+        .with_progress(move |progress| recorded.lock().unwrap().push(progress))
+        .build(WoaVersion::HM3)
+        .map(|_| ());
+    let events = events.lock().unwrap().clone();
+    (result, events)
+}
+
+fn steps(events: &[Progress], stage: Stage) -> Vec<(usize, usize)> {
+    events
+        .iter()
+        .filter(|progress| progress.stage == stage)
+        .map(|progress| (progress.completed, progress.total))
+        .collect()
+}
+
+fn linear_light() -> TextureMapBuilder {
+    builder().with_mip_generation(MipGenerationOptions {
+        linear_light: true,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn every_mip_level_is_reported() {
+    // a 64x64 texture has 7 mip levels, 6 of them are generated.
+    // DirectXTex generates the chain in one call, the float path reports every level
+    for (builder, generated) in [
+        (builder(), vec![(0, 6), (6, 6)]),
+        (linear_light(), (0..=6).map(|i| (i, 6)).collect::<Vec<_>>()),
+    ] {
+        let (result, events) = record(builder);
+        result.unwrap();
+
+        assert_eq!(steps(&events, Stage::GenerateMips), generated);
+        assert_eq!(
+            steps(&events, Stage::Encode),
+            (0..=7).map(|i| (i, 7)).collect::<Vec<_>>()
+        );
+
+        // the levels can finish in any order, but the count only goes up
+        let mut compressed = steps(&events, Stage::Compress);
+        assert!(compressed.iter().all(|(_, total)| *total == 7));
+        compressed.sort();
+        assert_eq!(compressed, (0..=7).map(|i| (i, 7)).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn cancelling_stops_between_mip_levels() {
+    for (stage, builder) in [
+        (Stage::GenerateMips, linear_light()),
+        (Stage::Encode, builder()),
+    ] {
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded = events.clone();
+        let result = builder
+            .with_cancellation(token)
+            .with_progress(move |progress| {
+                recorded.lock().unwrap().push(progress);
+                if progress.stage == stage && progress.completed == 2 {
+                    cancel.cancel();
+                }
+            })
+            .build(WoaVersion::HM3);
+
+        assert!(matches!(result, Err(TexturePackerError::Cancelled(_))));
+        let events = events.lock().unwrap();
+        let last = events.last().unwrap();
+        assert_eq!((last.stage, last.completed), (stage, 2));
+    }
+}
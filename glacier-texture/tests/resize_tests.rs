@@ -0,0 +1,67 @@
+use glacier_texture::atlas::AtlasData;
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+fn gradient(width: u32, height: u32) -> Vec<u8> {
+    (0..width * height)
+        .flat_map(|i| [(i % width * 4) as u8, (i / width * 4) as u8, 0x80, 0xFF])
+        .collect()
+}
+
+fn build(width: u32, height: u32, format: RenderFormat, texd: bool) -> TextureMap {
+    TextureMapBuilder::from_rgba8(width, height, &gradient(width, height))
+        .unwrap()
+        .with_format(format)
+        .with_mipblock1(texd)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+#[test]
+fn resized_drops_top_mips() {
+    let texture = build(64, 32, RenderFormat::BC1, true);
+    let resized = texture.resized(16).unwrap();
+
+    assert_eq!((resized.width(), resized.height()), (16, 8));
+    assert_eq!(resized.num_mip_levels(), texture.num_mip_levels() - 2);
+    for level in 0..resized.num_mip_levels() {
+        assert_eq!(
+            resized.mipmap(level).unwrap().data,
+            texture.mipmap(level + 2).unwrap().data
+        );
+    }
+}
+
+#[test]
+fn resized_text_only_drops_the_mips_assigned_to_the_texd() {
+    let texture = build(8, 8, RenderFormat::BC1, false);
+    assert_eq!(texture.num_mip_levels(), 4);
+
+    // the TEXT of a 4x4 BC1 texture skips its largest mip
+    let resized = texture.resized(4).unwrap();
+    assert!(!resized.has_mipblock1());
+    assert_eq!((resized.width(), resized.height()), (2, 2));
+    assert_eq!(resized.num_mip_levels(), 2);
+    assert_eq!(
+        resized.mipmap(0).unwrap().data,
+        texture.mipmap(2).unwrap().data
+    );
+}
+
+#[test]
+fn resized_keeps_an_aligned_atlas() {
+    let texture = TextureMapBuilder::from_rgba8(64, 64, &gradient(64, 64))
+        .unwrap()
+        .with_format(RenderFormat::BC3)
+        .with_atlas(AtlasData::new_grid(4, 4))
+        .build(WoaVersion::HM3)
+        .unwrap();
+
+    let resized = texture.resized(8).unwrap();
+    let atlas = resized.atlas().as_ref().unwrap();
+    assert_eq!((atlas.width, atlas.height), (4, 4));
+
+    assert!(texture.resized(2).is_err());
+}
@@ -0,0 +1,164 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::progress::{CancellationToken, ProgressHooks, Stage};
+use glacier_texture::scan::{self, TextureStatistics};
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+fn texture(format: RenderFormat) -> TextureMap {
+    let pixels = (0..32 * 32 * 4).map(|i| i as u8).collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(32, 32, &pixels)
+        .unwrap()
+        .with_format(format)
+        .with_mipblock1(false)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+fn write_text(path: &Path, format: RenderFormat) {
+    fs::write(path, texture(format).pack_to_vec().unwrap()).unwrap();
+}
+
+/// A directory with three BC1 textures, one R8G8B8A8 texture and a broken file.
+fn corpus(name: &str) -> (PathBuf, Vec<PathBuf>) {
+    let directory =
+        std::env::temp_dir().join(format!("glacier-texture-{name}-{}", std::process::id()));
+    fs::create_dir_all(directory.join("nested")).unwrap();
+    for name in ["a", "b", "nested/c"] {
+        write_text(&directory.join(format!("{name}.TEXT")), RenderFormat::BC1);
+    }
+    write_text(&directory.join("d.text"), RenderFormat::R8G8B8A8);
+    fs::write(directory.join("broken.TEXT"), [0u8; 16]).unwrap();
+    fs::write(directory.join("ignored.TEXD"), [0u8; 16]).unwrap();
+    let files = scan::find_texture_files(&directory).unwrap();
+    (directory, files)
+}
+
+#[test]
+fn directory_scan_counts_every_signature() {
+    let (directory, files) = corpus("scan");
+    let statistics = scan::scan_directory(&directory, WoaVersion::HM3).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(files.len(), 5);
+    assert_eq!(statistics.total(), 4);
+    assert_eq!(statistics.failures().len(), 1);
+    assert_eq!(statistics.failures()[0].0, "broken");
+
+    let entries = statistics.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].count, 3);
+    assert_eq!(entries[0].signature.format, RenderFormat::BC1);
+    assert_eq!(entries[0].signature.width, 32);
+    let mut examples = entries[0].examples.clone();
+    examples.sort();
+    assert_eq!(examples, ["a", "b", "c"]);
+    assert_eq!(entries[1].signature.format, RenderFormat::R8G8B8A8);
+
+    let csv = statistics.to_csv();
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.lines().nth(1).unwrap().starts_with("HM3,"));
+}
+
+#[test]
+fn histogram_does_not_depend_on_the_scan_order() {
+    let textures = [
+        ("c", RenderFormat::BC1),
+        ("f", RenderFormat::R8G8B8A8),
+        ("a", RenderFormat::BC1),
+        ("d", RenderFormat::R8G8B8A8),
+        ("e", RenderFormat::BC1),
+        ("b", RenderFormat::R8G8B8A8),
+    ]
+    .map(|(id, format)| (id, texture(format)));
+
+    let mut serial = TextureStatistics::new(2);
+    for (id, texture) in &textures {
+        serial.add(*id, texture);
+    }
+    // as the parallel scan does, fill two histograms in a different order and merge them
+    let (mut left, mut right) = (TextureStatistics::new(2), TextureStatistics::new(2));
+    for (id, texture) in textures.iter().rev().take(2) {
+        left.add(*id, texture);
+    }
+    for (id, texture) in textures.iter().rev().skip(2) {
+        right.add(*id, texture);
+    }
+    let merged = right.merge(left);
+
+    assert_eq!(serial.to_csv(), merged.to_csv());
+    for entry in serial.entries() {
+        assert_eq!(entry.count, 3);
+        let expected = match entry.signature.format {
+            RenderFormat::BC1 => ["a", "c"],
+            _ => ["b", "d"],
+        };
+        assert_eq!(entry.examples, expected);
+    }
+}
+
+#[test]
+fn scanned_files_are_added_to_an_existing_histogram() {
+    let (directory, files) = corpus("scan-files");
+    let mut statistics = TextureStatistics::new(1);
+    scan::scan_files(
+        &files,
+        WoaVersion::HM3,
+        &mut statistics,
+        &ProgressHooks::default(),
+    )
+    .unwrap();
+    scan::scan_files(
+        &files,
+        WoaVersion::HM3,
+        &mut statistics,
+        &ProgressHooks::default(),
+    )
+    .unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(statistics.total(), 8);
+    assert_eq!(statistics.failures().len(), 2);
+    let entries = statistics.entries();
+    assert_eq!(entries[0].count, 6);
+    assert_eq!(entries[0].examples.len(), 1);
+}
+
+#[test]
+fn scan_reports_every_file() {
+    let (directory, files) = corpus("scan-progress");
+    let reports = Arc::new(Mutex::new(vec![]));
+    let hooks = ProgressHooks::new().with_callback({
+        let reports = reports.clone();
+        move |progress| reports.lock().unwrap().push(progress)
+    });
+    let mut statistics = TextureStatistics::default();
+    scan::scan_files(&files, WoaVersion::HM3, &mut statistics, &hooks).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    let mut reports = reports.lock().unwrap().clone();
+    assert!(reports.iter().all(|progress| progress.stage == Stage::Scan));
+    assert_eq!(reports.pop().map(|p| (p.completed, p.total)), Some((5, 5)));
+    let mut completed = reports.iter().map(|p| p.completed).collect::<Vec<_>>();
+    completed.sort();
+    assert_eq!(completed, [0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn cancelled_scan_stops() {
+    let (directory, files) = corpus("scan-cancel");
+    let token = CancellationToken::new();
+    token.cancel();
+    let hooks = ProgressHooks::new().with_cancellation(token);
+
+    let mut statistics = TextureStatistics::default();
+    assert!(scan::scan_files(&files, WoaVersion::HM3, &mut statistics, &hooks).is_err());
+    assert_eq!(statistics.total(), 0);
+
+    let error = scan::scan_directory_with_hooks(&directory, WoaVersion::HM3, &hooks).unwrap_err();
+    fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::Interrupted);
+}
@@ -0,0 +1,59 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+fn builder() -> TextureMapBuilder {
+    let pixels = (0..256 * 256 * 4).map(|i| i as u8).collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(256, 256, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::R8G8B8A8)
+}
+
+fn build(builder: TextureMapBuilder) -> TextureMap {
+    builder.build(WoaVersion::HM3).unwrap()
+}
+
+#[test]
+fn text_only_build_keeps_only_the_text_mips() {
+    let full = build(builder());
+    let text_only = build(builder().with_text_only(true));
+    assert!(!text_only.has_mipblock1());
+
+    // the largest mip belongs in the TEXD, so it isn't stored at all
+    assert_eq!(full.text_scale(), 1);
+    let reread =
+        TextureMap::from_memory(&text_only.pack_to_vec().unwrap(), WoaVersion::HM3).unwrap();
+    for texture in [&text_only, &reread] {
+        assert_eq!(texture.num_mip_levels(), full.num_mip_levels() - 1);
+        let top = texture.mipmap(0).unwrap();
+        assert_eq!((top.width, top.height), (128, 128));
+        for level in 0..texture.num_mip_levels() {
+            assert_eq!(
+                texture.mipmap(level).unwrap().data,
+                full.mipmap(level + 1).unwrap().data
+            );
+        }
+    }
+}
+
+#[test]
+fn text_only_build_matches_text_only() {
+    let full = build(builder());
+    let expected = full.text_only().unwrap().pack_to_vec().unwrap();
+    for use_mipblock1 in [true, false] {
+        let text_only = builder().with_mipblock1(use_mipblock1).with_text_only(true);
+        assert_eq!(build(text_only).pack_to_vec().unwrap(), expected);
+    }
+}
+
+#[test]
+fn building_without_texd_keeps_the_full_chain() {
+    let full = build(builder());
+    let without_texd = build(builder().with_mipblock1(false));
+    assert!(!without_texd.has_mipblock1());
+
+    // every mip, including the 256x256 one, is stored in the TEXT
+    let text = without_texd.pack_to_vec().unwrap();
+    assert!(text.ends_with(&full.mipblock1().unwrap().data));
+}
@@ -0,0 +1,116 @@
+use glacier_texture::enums::{RenderFormat, TextureType};
+use glacier_texture::pack::TextureMapBuilder;
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+fn build(builder: TextureMapBuilder, format: RenderFormat) -> TextureMap {
+    builder.with_format(format).build(WoaVersion::HM3).unwrap()
+}
+
+fn flat(size: u32, pixel: [u8; 4], format: RenderFormat) -> TextureMap {
+    let pixels = pixel.repeat((size * size) as usize);
+    build(
+        TextureMapBuilder::from_rgba8(size, size, &pixels).unwrap(),
+        format,
+    )
+}
+
+#[test]
+fn thumbnail_uses_a_matching_mip() {
+    let texture = flat(64, [0x20, 0x40, 0x60, 0xFF], RenderFormat::R8G8B8A8);
+    let thumbnail = texture.thumbnail(16).unwrap();
+    assert_eq!(thumbnail.format, RenderFormat::R8G8B8A8);
+    assert_eq!((thumbnail.width, thumbnail.height), (16, 16));
+    assert_eq!(&thumbnail.data[..4], [0x20, 0x40, 0x60, 0xFF]);
+}
+
+#[test]
+fn thumbnail_between_mip_sizes_is_resized() {
+    let texture = flat(64, [0x20, 0x40, 0x60, 0xFF], RenderFormat::R8G8B8A8);
+    let thumbnail = texture.thumbnail(24).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (24, 24));
+    assert_eq!(thumbnail.data.len(), 24 * 24 * 4);
+    assert!(thumbnail.data.chunks_exact(4).all(|pixel| pixel
+        .iter()
+        .zip([0x20, 0x40, 0x60, 0xFF])
+        .all(|(a, b)| a.abs_diff(b) <= 1)));
+
+    // larger requests are capped at the texture size
+    let thumbnail = texture.thumbnail(1024).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (64, 64));
+}
+
+#[test]
+fn thumbnail_keeps_the_aspect_ratio() {
+    let pixels = [0u8; 64 * 16 * 4];
+    let texture = build(
+        TextureMapBuilder::from_rgba8(64, 16, &pixels).unwrap(),
+        RenderFormat::R8G8B8A8,
+    );
+    let thumbnail = texture.thumbnail(32).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (32, 8));
+}
+
+#[test]
+fn thumbnail_size_has_to_be_positive() {
+    let texture = flat(8, [0; 4], RenderFormat::R8G8B8A8);
+    assert!(texture.thumbnail(0).is_err());
+}
+
+#[test]
+fn normal_map_thumbnail_rebuilds_blue() {
+    let pixels = [128u8, 128].repeat(16 * 16);
+    let texture = build(
+        TextureMapBuilder::from_rg8(16, 16, &pixels)
+            .unwrap()
+            .with_texture_type(TextureType::Normal),
+        RenderFormat::R8G8,
+    );
+    let thumbnail = texture.thumbnail(8).unwrap();
+    for pixel in thumbnail.data.chunks_exact(4) {
+        assert!(pixel[2] >= 0xFE, "{pixel:?}");
+        assert_eq!(pixel[3], 0xFF, "{pixel:?}");
+    }
+}
+
+#[test]
+fn single_channel_thumbnail_is_grayscale() {
+    let pixels = [0x60u8; 16 * 16];
+    let texture = build(
+        TextureMapBuilder::from_r8(16, 16, &pixels).unwrap(),
+        RenderFormat::A8,
+    );
+    let thumbnail = texture.thumbnail(8).unwrap();
+    assert!(thumbnail
+        .data
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0x60, 0x60, 0x60, 0xFF]));
+}
+
+#[test]
+fn thumbnail_of_a_text_only_texture() {
+    let texture = flat(256, [0x80, 0x80, 0x80, 0xFF], RenderFormat::BC1)
+        .text_only()
+        .unwrap();
+    assert!(texture.width() < 256);
+    let thumbnail = texture.thumbnail(16).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (16, 16));
+}
+
+#[test]
+fn height_map_thumbnail_is_grayscale() {
+    // the height is read from red, the other channels and alpha don't show up in the preview
+    let pixels = [0x60u8, 0x10, 0xC0, 0x00].repeat(16 * 16);
+    let texture = build(
+        TextureMapBuilder::from_rgba8(16, 16, &pixels)
+            .unwrap()
+            .with_texture_type(TextureType::Height),
+        RenderFormat::R8G8B8A8,
+    );
+    let thumbnail = texture.thumbnail(8).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (8, 8));
+    assert!(thumbnail
+        .data
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0x60, 0x60, 0x60, 0xFF]));
+}
@@ -0,0 +1,322 @@
+use glacier_texture::enums::{RenderFormat, TextureFlags};
+use glacier_texture::header_info::TextureMapHeaderInfo;
+use glacier_texture::pack::{MipLevels, TextureMapBuilder};
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+#[derive(Copy, Clone)]
+enum Layout {
+    Linear,
+    Xbox360,
+    Morton,
+}
+
+fn noise(width: u32, height: u32) -> Vec<u8> {
+    let mut state = 0x1234_5678u32;
+    (0..width * height * 4)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+/// The layouts are only written for HM3, older versions drop the flags outside of the `unstable` feature.
+/// HM3 stores the flags right after the texture count, the type and the data size.
+const FLAGS_OFFSET: usize = 8;
+
+/// Sets the flag bit of a layout in a packed texture.
+fn set_layout_flag(data: &mut [u8], layout: Layout) {
+    data[FLAGS_OFFSET] |= match layout {
+        Layout::Linear => 0,
+        Layout::Xbox360 => 0x4,
+        Layout::Morton => 0x1,
+    };
+}
+
+/// The flags of `texture` with the layout flag set. The layout flags have no setter outside of the
+/// `unstable` feature, so they are set in the packed texture and read back.
+fn layout_flags(texture: &TextureMap, layout: Layout) -> TextureFlags {
+    let mut data = texture.pack_to_vec().unwrap();
+    set_layout_flag(&mut data, layout);
+    TextureMap::from_memory(&data, WoaVersion::HM3)
+        .unwrap()
+        .flags()
+}
+
+fn builder(width: u32, height: u32, format: RenderFormat) -> TextureMapBuilder {
+    TextureMapBuilder::from_rgba8(width, height, &noise(width, height))
+        .unwrap()
+        .with_format(format)
+        .with_mipblock1(false)
+}
+
+fn build_for(
+    width: u32,
+    height: u32,
+    format: RenderFormat,
+    layout: Layout,
+    mip_levels: MipLevels,
+) -> TextureMap {
+    let build = |builder: TextureMapBuilder| {
+        builder
+            .with_num_mip_levels(mip_levels)
+            .build(WoaVersion::HM3)
+            .unwrap()
+    };
+    let linear = build(builder(width, height, format));
+    match layout {
+        Layout::Linear => linear,
+        _ => build(builder(width, height, format).with_flags(layout_flags(&linear, layout))),
+    }
+}
+
+fn build(width: u32, height: u32, format: RenderFormat, layout: Layout) -> TextureMap {
+    build_for(width, height, format, layout, MipLevels::All)
+}
+
+fn assert_round_trip(width: u32, height: u32, format: RenderFormat, layout: Layout) {
+    let linear = build(width, height, format, Layout::Linear);
+    let tiled = build(width, height, format, layout);
+
+    // the stored data has to change, otherwise the test proves nothing
+    assert_ne!(
+        linear.pack_to_vec().unwrap(),
+        tiled.pack_to_vec().unwrap(),
+        "{format:?} {width}x{height} is stored linearly"
+    );
+
+    let reread = TextureMap::from_memory(&tiled.pack_to_vec().unwrap(), WoaVersion::HM3).unwrap();
+    assert_eq!(reread.num_mip_levels(), linear.num_mip_levels());
+    for level in 0..linear.num_mip_levels() {
+        let expected = linear.mipmap(level).unwrap().data;
+        assert_eq!(
+            tiled.mipmap(level).unwrap().data,
+            expected,
+            "{format:?} {width}x{height} mip {level}"
+        );
+        assert_eq!(
+            reread.mipmap(level).unwrap().data,
+            expected,
+            "{format:?} {width}x{height} mip {level} after rereading"
+        );
+    }
+}
+
+#[test]
+fn xbox_360_round_trip() {
+    assert_round_trip(32, 32, RenderFormat::R8G8B8A8, Layout::Xbox360);
+    assert_round_trip(128, 128, RenderFormat::BC3, Layout::Xbox360);
+}
+
+#[test]
+fn xbox_360_rejects_unpadded_surfaces() {
+    // a 64x16 surface is padded to 64x32, half of the tiled surface is missing
+    let linear = build_for(
+        64,
+        16,
+        RenderFormat::R8G8B8A8,
+        Layout::Linear,
+        MipLevels::Limit(1),
+    );
+    let mut data = linear.pack_to_vec().unwrap();
+    set_layout_flag(&mut data, Layout::Xbox360);
+    let texture = TextureMap::from_memory(&data, WoaVersion::HM3).unwrap();
+    assert!(texture.mipmap(0).is_err());
+}
+
+#[test]
+#[cfg(not(feature = "unstable"))]
+fn swizzled_textures_are_stored_as_is() {
+    // the Morton layout is only used with the unstable feature, until it is checked against console data
+    let build = |layout| build_for(32, 32, RenderFormat::R8G8B8A8, layout, MipLevels::Limit(1));
+    let linear = build(Layout::Linear);
+    let swizzled = build(Layout::Morton);
+    assert_eq!(stored_mip(&swizzled), stored_mip(&linear));
+    assert_eq!(
+        swizzled.mipmap(0).unwrap().data,
+        linear.mipmap(0).unwrap().data
+    );
+}
+
+#[test]
+fn xbox_360_round_trip_unaligned() {
+    // neither size is a multiple of the 32x32 block tiles
+    assert_round_trip(16, 8, RenderFormat::R8G8B8A8, Layout::Xbox360);
+    assert_round_trip(64, 16, RenderFormat::BC1, Layout::Xbox360);
+    assert_round_trip(4, 64, RenderFormat::R8G8, Layout::Xbox360);
+    assert_round_trip(64, 2, RenderFormat::A8, Layout::Xbox360);
+}
+
+#[test]
+#[cfg(feature = "unstable")]
+fn morton_round_trip() {
+    assert_round_trip(32, 32, RenderFormat::R8G8B8A8, Layout::Morton);
+    assert_round_trip(64, 64, RenderFormat::BC1, Layout::Morton);
+}
+
+#[test]
+#[cfg(feature = "unstable")]
+fn morton_round_trip_non_square() {
+    assert_round_trip(32, 8, RenderFormat::R8G8, Layout::Morton);
+    assert_round_trip(32, 16, RenderFormat::BC3, Layout::Morton);
+    // a 2x2 BC1 mip still fills a whole block
+    assert_round_trip(8, 8, RenderFormat::BC1, Layout::Morton);
+}
+
+#[test]
+#[cfg(feature = "unstable")]
+fn morton_rejects_non_power_of_two_sizes() {
+    let flags = layout_flags(
+        &build(32, 32, RenderFormat::R8G8B8A8, Layout::Linear),
+        Layout::Morton,
+    );
+    let result = builder(24, 24, RenderFormat::R8G8B8A8).with_flags(flags);
+    assert!(result.build(WoaVersion::HM3).is_err());
+}
+
+/// Reference port of `XGAddress2DTiledOffset` from the Xbox 360 SDK, the forward mapping from a block
+/// position to its index in the tiled surface. The library only implements the inverse mapping.
+fn xbox_reference_offset(x: usize, y: usize, width: usize, block_size: usize) -> usize {
+    let aligned_width = (width + 31) & !31;
+    let log_bpp = (block_size >> 2) + ((block_size >> 1) >> (block_size >> 2));
+    let macro_offset = ((x >> 5) + (y >> 5) * (aligned_width >> 5)) << (log_bpp + 7);
+    let micro_offset = ((x & 7) + ((y & 6) << 2)) << log_bpp;
+    let offset = macro_offset
+        + ((micro_offset & !15) << 1)
+        + (micro_offset & 15)
+        + ((y & 8) << (3 + log_bpp))
+        + ((y & 1) << 4);
+    (((offset & !511) << 3)
+        + ((offset & 448) << 2)
+        + (offset & 63)
+        + ((y & 16) << 7)
+        + (((((y & 8) >> 2) + (x >> 3)) & 3) << 6))
+        >> log_bpp
+}
+
+/// Reference Morton index of a square surface, the bits of x and y interleaved with x in the lowest bit.
+fn morton_reference_index(x: usize, y: usize) -> usize {
+    (0..usize::BITS as usize / 2)
+        .map(|bit| ((x >> bit) & 1) << (2 * bit) | ((y >> bit) & 1) << (2 * bit + 1))
+        .sum()
+}
+
+/// Tiles a single surface whose tiled size equals its linear size with the reference mappings.
+fn reference_tile(layout: Layout, format: RenderFormat, width: usize, linear: &[u8]) -> Vec<u8> {
+    let (block_width, block_size) = match format {
+        RenderFormat::R8G8B8A8 => (width, 4),
+        RenderFormat::BC1 => (width / 4, 8),
+        _ => unimplemented!("{format:?} has no reference layout"),
+    };
+    let mut tiled = vec![0; linear.len()];
+    for (index, block) in linear.chunks_exact(block_size).enumerate() {
+        let (x, y) = (index % block_width, index / block_width);
+        let target = match layout {
+            Layout::Linear => index,
+            Layout::Xbox360 => xbox_reference_offset(x, y, block_width, block_size),
+            Layout::Morton => morton_reference_index(x, y),
+        };
+        tiled[target * block_size..(target + 1) * block_size].copy_from_slice(block);
+    }
+    if let Layout::Xbox360 = layout {
+        // the Xbox 360 stores 32-bit words for RGBA8 and 16-bit words for the block formats
+        let word_size = if format == RenderFormat::R8G8B8A8 {
+            4
+        } else {
+            2
+        };
+        tiled.chunks_exact_mut(word_size).for_each(<[u8]>::reverse);
+    }
+    tiled
+}
+
+/// The stored data of a single mip HM3 texture, as it is before LZ4 compression.
+fn stored_mip(texture: &TextureMap) -> Vec<u8> {
+    let TextureMapHeaderInfo::V3(header) = texture.header_info().unwrap() else {
+        panic!("not a HM3 texture");
+    };
+    let data = texture.pack_to_vec().unwrap();
+    let block = &data[data.len() - header.compressed_mip_sizes[0] as usize..];
+    lz4::block::decompress(block, Some(header.mip_sizes[0] as i32)).unwrap()
+}
+
+/// Compares the stored data of a tiled texture against its linear pixels tiled by the reference mappings.
+fn assert_matches_reference(size: u32, format: RenderFormat, layout: Layout) {
+    let build = |layout| build_for(size, size, format, layout, MipLevels::Limit(1));
+    let linear = build(Layout::Linear);
+    let tiled = build(layout);
+    let pixels = linear.mipmap(0).unwrap().data;
+
+    assert_eq!(
+        stored_mip(&tiled),
+        reference_tile(layout, format, size as usize, &pixels),
+        "{format:?} {size}x{size} is tiled differently"
+    );
+    assert_eq!(
+        tiled.mipmap(0).unwrap().data,
+        pixels,
+        "{format:?} {size}x{size}"
+    );
+}
+
+#[test]
+fn xbox_360_reference_offsets() {
+    // a 32x32 surface of 4 byte texels, like R8G8B8A8
+    for ((x, y), offset) in [
+        ((0, 0), 0),
+        ((1, 0), 1),
+        ((7, 0), 11),
+        ((8, 0), 16),
+        ((0, 1), 4),
+        ((0, 2), 64),
+        ((0, 8), 288),
+        ((5, 17), 525),
+        ((31, 31), 991),
+    ] {
+        assert_eq!(xbox_reference_offset(x, y, 32, 4), offset, "({x}, {y})");
+    }
+    // a 32x32 surface of 8 byte blocks, like BC1
+    for ((x, y), offset) in [
+        ((1, 0), 1),
+        ((7, 0), 37),
+        ((8, 0), 8),
+        ((0, 1), 2),
+        ((0, 8), 528),
+        ((5, 17), 291),
+        ((31, 31), 1007),
+    ] {
+        assert_eq!(xbox_reference_offset(x, y, 32, 8), offset, "({x}, {y})");
+    }
+}
+
+#[test]
+fn xbox_360_matches_the_reference() {
+    // both surfaces fill whole 4 KiB pages, so they aren't padded
+    assert_matches_reference(32, RenderFormat::R8G8B8A8, Layout::Xbox360);
+    assert_matches_reference(128, RenderFormat::BC1, Layout::Xbox360);
+}
+
+#[test]
+#[cfg(feature = "unstable")]
+fn morton_reference_indices() {
+    for ((x, y), index) in [
+        ((1, 0), 1),
+        ((0, 1), 2),
+        ((1, 1), 3),
+        ((2, 0), 4),
+        ((3, 5), 39),
+        ((15, 15), 255),
+    ] {
+        assert_eq!(morton_reference_index(x, y), index, "({x}, {y})");
+    }
+}
+
+#[test]
+#[cfg(feature = "unstable")]
+fn morton_matches_the_reference() {
+    assert_matches_reference(16, RenderFormat::R8G8B8A8, Layout::Morton);
+    assert_matches_reference(64, RenderFormat::BC1, Layout::Morton);
+}
@@ -0,0 +1,51 @@
+use glacier_texture::enums::RenderFormat;
+use glacier_texture::pack::{TextureMapBuilder, TexturePackerError};
+use glacier_texture::texture_map::TextureMap;
+use glacier_texture::WoaVersion;
+
+fn build(size: u32, texd: bool) -> TextureMap {
+    let pixels = (0..size * size)
+        .flat_map(|i| [(i % size) as u8, (i / size) as u8, 0x40, 0xFF])
+        .collect::<Vec<_>>();
+    TextureMapBuilder::from_rgba8(size, size, &pixels)
+        .unwrap()
+        .with_format(RenderFormat::R8G8B8A8)
+        .with_mipblock1(texd)
+        .build(WoaVersion::HM3)
+        .unwrap()
+}
+
+#[test]
+fn transcoded_keeps_the_mip_chain() {
+    let texture = build(256, true);
+    let transcoded = texture.transcoded(RenderFormat::BC1).unwrap();
+
+    assert_eq!(transcoded.format(), RenderFormat::BC1);
+    assert_eq!(transcoded.num_mip_levels(), texture.num_mip_levels());
+    assert_eq!(transcoded.flags().bits(), texture.flags().bits());
+    assert_eq!(transcoded.texture_type(), texture.texture_type());
+    // BC1 stores half a byte per pixel
+    assert_eq!(
+        transcoded.mipmap(0).unwrap().data.len() * 8,
+        texture.mipmap(0).unwrap().data.len()
+    );
+}
+
+#[test]
+fn transcoded_needs_the_texd_of_a_scaled_texture() {
+    let texture = build(256, false);
+    assert!(matches!(
+        texture.transcoded(RenderFormat::BC1),
+        Err(TexturePackerError::MissingTexd(1))
+    ));
+
+    // the TEXT of a small texture holds the full chain
+    let texture = build(64, false);
+    assert_eq!(
+        texture
+            .transcoded(RenderFormat::BC1)
+            .unwrap()
+            .num_mip_levels(),
+        texture.num_mip_levels()
+    );
+}